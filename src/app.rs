@@ -1,91 +1,308 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::sync::Arc;
 
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Tabs},
+    widgets::{Block, Borders, Clear, Tabs},
     Frame,
 };
 
-use crate::components::CommandComponent;
-use crate::tabs::{DebugTab, HistoryTab, MainTab};
+use crate::components::{
+    Action, CommandComponent, CommandPalette, CommandType, Component, HelpOverlay, PaletteEntry,
+    Toast, Toasts, Vim,
+};
+use crate::events::{Message, MessageSender};
+use crate::http::RequestContext;
+use crate::keymap::{HelpSection, Keymap, Scope, Shortcut};
+use crate::tabs::{CookiesTab, DebugTab, HistoryTab, MainTab};
+use crate::theme::Theme;
+
+// Collection that gets loaded on startup
+pub const COLLECTION_PATH: &str = "./config.json";
+
+// Keys that differ from the defaults, the file is optional
+pub const KEYMAP_PATH: &str = "./keymap.toml";
+
+// Colors that differ from the dark preset, the file is optional
+pub const THEME_PATH: &str = "./theme.toml";
+
+// Tabs that can be switched to, in order
+const TABS: [usize; 4] = [0, 1, 3, 4];
 
 pub struct App<'a> {
     main_tab: MainTab<'a>,
     history_tab: HistoryTab,
     debug_tab: DebugTab,
+    cookies_tab: CookiesTab<'a>,
     cmdbar: CommandComponent,
+    toasts: Toasts,
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+
+    palette: CommandPalette<'a>,
+    help: HelpOverlay,
+
+    // Only there with the vim keymap
+    vim: Option<Vim<'a>>,
     do_quit: bool,
     current_tab: usize,
 }
 
 impl App<'_> {
-    pub fn new() -> Self {
-        let main_tab = MainTab::new();
-        let history_tab = HistoryTab::new();
-        let debug_tab = DebugTab::new();
-        let cmdbar = CommandComponent::new(main_tab.current_cmds.clone());
+    pub fn new(tx: MessageSender) -> Self {
+        let mut toasts = Toasts::default();
+
+        let keymap = Arc::new(Keymap::load(KEYMAP_PATH).unwrap_or_else(|e| {
+            log::error!("Couldn't load {}: {}", KEYMAP_PATH, e);
+            toasts.push(Toast::from(e));
+
+            Keymap::default()
+        }));
+
+        let theme = Arc::new(Theme::load(THEME_PATH).unwrap_or_else(|e| {
+            log::error!("Couldn't load {}: {}", THEME_PATH, e);
+            toasts.push(Toast::from(e));
+
+            Theme::default()
+        }));
+
+        let context = RequestContext::new(COLLECTION_PATH);
+        let main_tab = MainTab::new(
+            COLLECTION_PATH,
+            context.clone(),
+            keymap.clone(),
+            theme.clone(),
+            tx,
+        );
+        let history_tab = HistoryTab::new(context.history.clone(), keymap.clone(), theme.clone());
+        let debug_tab = DebugTab::new(context.canonical_requests, theme.clone());
+        let cookies_tab = CookiesTab::new(context.cookies, keymap.clone(), theme.clone());
+        let cmdbar = CommandComponent::new(main_tab.commands(), theme.clone());
+        let vim = keymap.is_vim().then(|| Vim::new(keymap.clone()));
+        let help = HelpOverlay::new(keymap.clone(), theme.clone());
+        let palette = CommandPalette::new(theme.clone());
 
         Self {
             main_tab,
             history_tab,
             debug_tab,
+            cookies_tab,
             cmdbar,
+            toasts,
+            keymap,
+            theme,
+            palette,
+            help,
+            vim,
             do_quit: false,
             current_tab: 0,
         }
     }
 
-    pub fn event(&mut self, ev: KeyEvent) {
-        match ev {
-            // Quit by hitting 'q' or 'ctrl-c'
-            KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::NONE,
+    fn key(&mut self, ev: KeyEvent) {
+        // Typed text must not trigger the global shortcuts
+        let action = match self.global_action(ev) {
+            Some(action) if !self.is_editing() => action,
+            _ => match self.current_tab {
+                0 => self.main_tab.event(ev),
+                1 => self.history_tab.event(ev),
+                3 => self.debug_tab.event(ev),
+                4 => self.cookies_tab.event(ev),
+                _ => Action::None,
+            },
+        };
+
+        self.dispatch(action);
+    }
+
+    pub fn message(&mut self, message: Message) {
+        match message {
+            Message::RequestFinished { endpoint, id } => {
+                self.main_tab.request_finished(&endpoint, id);
+            }
+            Message::CollectionChanged => {
+                if let Err(e) = self.main_tab.reload_collection() {
+                    self.toasts.push(e.into());
+                }
+            }
+            Message::Toast(toast) => self.toasts.push(toast),
+        }
+    }
+
+    // Whether the screen changes on its own, without any event coming in
+    pub fn is_animating(&self) -> bool {
+        // New log lines only show up on the next draw
+        self.main_tab.is_animating() || self.current_tab == 3
+    }
+
+    fn global_action(&self, ev: KeyEvent) -> Option<Action> {
+        match self.keymap.lookup(ev, Scope::Global)? {
+            Shortcut::Quit => Some(Action::Quit),
+            Shortcut::Palette => Some(Action::OpenPalette),
+            Shortcut::Help => Some(Action::OpenHelp),
+            Shortcut::MainTab => Some(Action::SwitchTab(0)),
+            Shortcut::HistoryTab => Some(Action::SwitchTab(1)),
+            Shortcut::DebugTab => Some(Action::SwitchTab(3)),
+            Shortcut::CookiesTab => Some(Action::SwitchTab(4)),
+            _ => None,
+        }
+    }
+
+    // Actions left over by the tabs, the command bar is refreshed after each
+    // one as focus may have moved
+    fn dispatch(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.do_quit = true,
+            Action::SwitchTab(tab) => self.current_tab = tab,
+            Action::NextTab | Action::PreviousTab => {
+                let i = TABS.iter().position(|t| *t == self.current_tab);
+                let i = match action {
+                    Action::NextTab => i.map_or(0, |i| (i + 1) % TABS.len()),
+                    _ => i.map_or(0, |i| (i + TABS.len() - 1) % TABS.len()),
+                };
+                self.current_tab = TABS[i];
             }
-            | KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-            } => {
-                self.do_quit = true;
+            Action::Repeat(ev, times) => {
+                for i in 0..times {
+                    // The key may have opened a text box, like `/`
+                    if i > 0 && self.is_editing() {
+                        break;
+                    }
+                    self.key(ev);
+                }
                 return;
             }
-            // Change to Tab 1
-            KeyEvent {
-                code: KeyCode::Char('1'),
-                modifiers: KeyModifiers::NONE,
-            } => {
-                self.current_tab = 0;
-                self.cmdbar.update_cmd(0);
+            Action::OpenPalette => {
+                let entries = self.palette_entries();
+                self.palette.open(entries);
             }
-            // Change to Tab 2
-            KeyEvent {
-                code: KeyCode::Char('2'),
-                modifiers: KeyModifiers::NONE,
-            } => {
-                self.current_tab = 1;
-                self.cmdbar.update_cmd(1);
+            Action::OpenHelp => {
+                let sections = self.help_sections();
+                self.help.open(sections);
             }
-            // Change to Tab 4
-            KeyEvent {
-                code: KeyCode::Char('4'),
-                modifiers: KeyModifiers::NONE,
-            } => {
-                self.current_tab = 3;
-                self.cmdbar.update_cmd(1);
+            // Its key is pressed once the pane the shortcut works in is shown
+            Action::RunShortcut(shortcut) => {
+                let ev = match self.keymap.key_event(shortcut) {
+                    Some(ev) => ev,
+                    None => return,
+                };
+
+                match Keymap::scope(shortcut) {
+                    Scope::MainTab => self.current_tab = 0,
+                    Scope::Tree => self.dispatch(Action::FocusTree),
+                    Scope::MainPane | Scope::Response => self.dispatch(Action::FocusMainPane),
+                    Scope::Cookies => self.current_tab = 4,
+                    Scope::Global | Scope::Navigation | Scope::SearchBar => {}
+                }
+
+                self.dispatch(Action::Repeat(ev, 1));
+                return;
             }
+            // Only come from the palette, the main tab handles them otherwise
+            Action::FocusTree | Action::FocusMainPane | Action::SelectEndpoint(_) => {
+                self.current_tab = 0;
+
+                let action = self.main_tab.dispatch(action);
+                self.dispatch(action);
+                return;
+            }
+            Action::Toast(toast) => self.toasts.push(toast),
             _ => {}
-        };
+        }
+
+        self.cmdbar.cmds_from(self.commands());
+    }
 
-        if self.current_tab == 0 {
-            self.main_tab.event(ev);
-            self.cmdbar.cmds_from(self.main_tab.current_cmds.clone());
+    // Keys that work in the focused pane of the current tab, and vim's
+    fn help_sections(&self) -> Vec<HelpSection> {
+        let mut sections = self.keymap.help(self.scope());
+        if self.vim.is_some() {
+            sections.push(Vim::help());
         }
+
+        sections
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) -> std::io::Result<()> {
+    // Everything the command palette offers, commands before endpoints
+    fn palette_entries(&mut self) -> Vec<PaletteEntry> {
+        let mut entries = self
+            .keymap
+            .commands()
+            .into_iter()
+            .filter(|(shortcut, _, _)| *shortcut != Shortcut::Palette)
+            .map(|(shortcut, description, keys)| {
+                PaletteEntry::command(description, keys, Action::RunShortcut(shortcut))
+            })
+            .collect::<Vec<PaletteEntry>>();
+
+        entries.extend(self.main_tab.endpoints().iter().map(PaletteEntry::endpoint));
+
+        entries
+    }
+
+    pub fn is_quit(&self) -> bool {
+        self.do_quit
+    }
+
+    pub fn draw_tabs<B: Backend>(&self, f: &mut Frame<B>, r: Rect) {
+        let titles = [
+            String::from("Main"),
+            String::from("History"),
+            String::from("About"),
+            String::from("Debug"),
+            String::from("Cookies"),
+        ];
+
+        let titles = titles
+            .iter()
+            .map(|item| Spans::from(vec![Span::styled(item, Style::default())]))
+            .collect();
+
+        let tabs = Tabs::new(titles)
+            .select(self.current_tab)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+        f.render_widget(tabs, r);
+    }
+}
+
+impl Component for App<'_> {
+    // Everything is applied here, nothing is left over for the caller
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if ev.code == KeyCode::Esc && !self.toasts.is_empty() && !self.is_editing() {
+            self.toasts.dismiss();
+            return Action::None;
+        }
+
+        if self.palette.is_editing() {
+            let action = self.palette.event(ev);
+            self.dispatch(action);
+            return Action::None;
+        }
+
+        if self.help.is_editing() {
+            let action = self.help.event(ev);
+            self.dispatch(action);
+            return Action::None;
+        }
+
+        // The vim keys work wherever the global shortcuts do
+        let to_vim = self.vim.as_ref().is_some_and(|vim| vim.is_editing()) || !self.is_editing();
+
+        let action = match &mut self.vim {
+            Some(vim) if to_vim => vim.event(ev),
+            _ => Action::Repeat(ev, 1),
+        };
+
+        self.dispatch(action);
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -93,7 +310,7 @@ impl App<'_> {
                 Constraint::Min(2),    // Main
                 Constraint::Length(2), // Cmdbar
             ])
-            .split(f.size());
+            .split(r);
 
         self.draw_tabs(f, chunks[0]);
 
@@ -109,34 +326,56 @@ impl App<'_> {
             3 => {
                 self.debug_tab.draw(f, chunks[1]);
             }
+            4 => {
+                self.cookies_tab.draw(f, chunks[1]);
+                self.cmdbar.draw(f, chunks[2]);
+            }
             _ => {}
         };
 
-        Ok(())
-    }
+        if let Some(vim) = self.vim.as_mut().filter(|vim| vim.is_editing()) {
+            f.render_widget(Clear, chunks[2]);
+            vim.draw(f, chunks[2]);
+        }
 
-    pub fn is_quit(&self) -> bool {
-        self.do_quit
+        self.palette.draw(f, r);
+        self.help.draw(f, r);
+        self.toasts.draw(f, chunks[1], &self.theme);
     }
 
-    pub fn draw_tabs<B: Backend>(&self, f: &mut Frame<B>, r: Rect) {
-        let titles = vec![
-            String::from("Main"),
-            String::from("History"),
-            String::from("About"),
-            String::from("Debug"),
-        ];
+    fn commands(&self) -> Vec<CommandType> {
+        match self.current_tab {
+            0 => self.main_tab.commands(),
+            1 => self.history_tab.commands(),
+            3 => self.debug_tab.commands(),
+            4 => self.cookies_tab.commands(),
+            _ => Vec::new(),
+        }
+    }
 
-        let titles = titles
-            .iter()
-            .map(|item| Spans::from(vec![Span::styled(item, Style::default())]))
-            .collect();
+    // Keys that work in the focused pane of the current tab
+    fn scope(&self) -> Option<Scope> {
+        match self.current_tab {
+            0 => self.main_tab.scope(),
+            1 => self.history_tab.scope(),
+            3 => self.debug_tab.scope(),
+            4 => self.cookies_tab.scope(),
+            _ => None,
+        }
+    }
 
-        let tabs = Tabs::new(titles)
-            .select(self.current_tab)
-            .block(Block::default().borders(Borders::ALL))
-            .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    fn is_editing(&self) -> bool {
+        if self.palette.is_editing()
+            || self.help.is_editing()
+            || self.vim.as_ref().is_some_and(|vim| vim.is_editing())
+        {
+            return true;
+        }
 
-        f.render_widget(tabs, r);
+        match self.current_tab {
+            0 => self.main_tab.is_editing(),
+            4 => self.cookies_tab.is_editing(),
+            _ => false,
+        }
     }
 }
@@ -1,12 +1,16 @@
+use crossterm::event::KeyEvent;
+use std::sync::Arc;
 use tui::{
     backend::Backend,
     layout::{Alignment, Rect},
-    style::{Color, Style},
     text::{Span, Spans},
     widgets::{Block, Paragraph},
     Frame,
 };
 
+use crate::components::{Action, Component};
+use crate::theme::Theme;
+
 #[derive(Clone)]
 pub enum CommandType {
     // Command containing a value
@@ -17,37 +21,37 @@ pub enum CommandType {
 }
 
 pub struct CommandComponent {
-    tab: usize,
     list: Vec<CommandType>,
+    theme: Arc<Theme>,
 }
 
 impl CommandComponent {
-    pub fn new(list: Vec<CommandType>) -> Self {
-        Self { tab: 0, list }
+    pub fn new(list: Vec<CommandType>, theme: Arc<Theme>) -> Self {
+        Self { list, theme }
     }
 
     pub fn cmds_from(&mut self, input: Vec<CommandType>) {
         self.list = input;
     }
 
-    // TODO: Do not hardcode commands, but get them from components
     fn get_cmds(&self) -> Vec<CommandType> {
         self.list.clone()
     }
+}
 
-    pub fn update_cmd(&mut self, current_tab: usize) {
-        self.tab = current_tab;
+impl Component for CommandComponent {
+    // Only shows the keys of the focused pane, it never takes any itself
+    fn event(&mut self, _ev: KeyEvent) -> Action {
+        Action::None
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
         let cloned = self.get_cmds();
 
         let spans_inside = cloned
             .iter()
             .map(|item| match item {
-                CommandType::Command(val) => {
-                    Span::styled(val, Style::default().bg(Color::Blue).fg(Color::Black))
-                }
+                CommandType::Command(val) => Span::styled(val, self.theme.command()),
                 CommandType::Break => Span::raw(" "),
             })
             .collect::<Vec<Span>>();
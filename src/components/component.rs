@@ -0,0 +1,68 @@
+use crossterm::event::KeyEvent;
+use tui::{backend::Backend, layout::Rect, Frame};
+
+use crate::components::{CommandType, Toast};
+use crate::keymap::{Scope, Shortcut};
+
+// What should happen after a component handled a key. Components only change
+// their own state, anything that reaches further is returned as an action and
+// applied by whoever owns them: the tab for its panes and popups, the app for
+// everything left over.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    None,
+
+    // App
+    Quit,
+    SwitchTab(usize),
+    NextTab,
+    PreviousTab,
+    OpenPalette,
+    OpenHelp,
+    Toast(Toast),
+
+    // A shortcut picked in the palette, run where it works
+    RunShortcut(Shortcut),
+
+    // The key handled again as if it was pressed that many times
+    Repeat(KeyEvent, usize),
+
+    // Main tab
+    FocusTree,
+    FocusMainPane,
+    SelectionChanged,
+    SelectEndpoint(String),
+    OpenAddEndpoint,
+    CloseAddEndpoint,
+    AddEndpoint,
+    OpenMethodPicker,
+    CloseMethodPicker,
+    SetMethod(String),
+    SetBody(String),
+    SendRequest,
+    CancelRequest,
+}
+
+pub trait Component {
+    fn event(&mut self, ev: KeyEvent) -> Action;
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect);
+
+    // Shortcuts shown in the command bar while the component has focus
+    fn commands(&self) -> Vec<CommandType> {
+        Vec::new()
+    }
+
+    // Keymap scope of the focused part, the help lists the keys of it
+    fn scope(&self) -> Option<Scope> {
+        None
+    }
+
+    fn set_focus(&mut self, _focused: bool) {}
+
+    // Typing into a text box, keys must reach the component before any
+    // global shortcut
+    fn is_editing(&self) -> bool {
+        false
+    }
+}
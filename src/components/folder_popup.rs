@@ -1,17 +1,21 @@
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::sync::Arc;
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, BorderType, Borders, Clear},
     Frame,
 };
 use tui_textarea::TextArea;
 
+use crate::components::{Action, Component};
+use crate::theme::Theme;
+
 #[derive(PartialEq)]
 enum Focus {
     Name,
-    URL,
+    Url,
     OkButton,
 }
 
@@ -20,37 +24,96 @@ pub struct FolderPopup<'a> {
     name_textbox: TextArea<'a>,
     url_textbox: TextArea<'a>,
     focus: Focus,
-    pub save_endpoint: bool,
+    theme: Arc<Theme>,
 }
 
 impl FolderPopup<'_> {
-    pub fn new() -> Self {
+    pub fn new(theme: Arc<Theme>) -> Self {
         Self {
             is_open: false,
             name_textbox: TextArea::default(),
             url_textbox: TextArea::default(),
             focus: Focus::Name,
-            save_endpoint: false,
+            theme,
         }
     }
 
-    pub fn save(&mut self) {
-        self.save_endpoint = true;
-    }
-
-    pub fn is_saved(&self) -> bool {
-        self.save_endpoint
-    }
-
     pub fn close(&mut self) {
-        self.save_endpoint = false;
         self.is_open = false;
         self.name_textbox = TextArea::default();
         self.url_textbox = TextArea::default();
         self.focus = Focus::Name;
     }
+}
+
+// Area of the given size in percent in the middle of `r`, for popups
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(popup_layout[1])[1]
+}
+
+impl Component for FolderPopup<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if ev.code == KeyCode::Esc {
+            return Action::CloseAddEndpoint;
+        }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        match self.focus {
+            Focus::Name => {
+                if ev.code != KeyCode::Up && ev.code != KeyCode::Enter {
+                    self.name_textbox.input(ev);
+                }
+
+                if ev.code == KeyCode::Down {
+                    self.focus = Focus::Url;
+                }
+            }
+            Focus::Url => {
+                self.url_textbox.input(ev);
+
+                if ev.code == KeyCode::Up {
+                    self.focus = Focus::Name;
+                }
+
+                if ev.code == KeyCode::Down {
+                    self.focus = Focus::OkButton;
+                }
+            }
+            Focus::OkButton => {
+                if ev.code == KeyCode::Up {
+                    self.focus = Focus::Url;
+                }
+                if ev.code == KeyCode::Enter {
+                    return Action::AddEndpoint;
+                }
+            }
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
         if self.is_open {
             let block = Block::default().borders(Borders::ALL).title("Add endpoint");
             let block_inner = block.inner(r);
@@ -71,28 +134,29 @@ impl FolderPopup<'_> {
                 BorderType::Plain
             };
 
-            let url_border_type = if self.focus == Focus::URL {
+            let url_border_type = if self.focus == Focus::Url {
                 BorderType::Thick
             } else {
                 BorderType::Plain
             };
 
-            let ok_button_fill = if self.focus == Focus::OkButton {
-                Color::Gray
+            let ok_button_style = if self.focus == Focus::OkButton {
+                self.theme.selected()
             } else {
-                Color::Black
+                Style::default()
             };
 
             let ok_button = Block::default()
                 .borders(Borders::ALL)
                 .title("OK")
                 .title_alignment(Alignment::Center)
-                .style(Style::default().bg(ok_button_fill));
+                .style(ok_button_style);
 
             self.name_textbox.set_block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_type(name_border_type)
+                    .border_style(self.theme.border(self.focus == Focus::Name))
                     .title("Enter endpoint name"),
             );
 
@@ -100,6 +164,7 @@ impl FolderPopup<'_> {
                 Block::default()
                     .borders(Borders::ALL)
                     .border_type(url_border_type)
+                    .border_style(self.theme.border(self.focus == Focus::Url))
                     .title("Enter endpoint URL"),
             );
 
@@ -123,64 +188,4 @@ impl FolderPopup<'_> {
             f.render_widget(ok_button, layout[2]);
         }
     }
-
-    pub fn event(&mut self, ev: KeyEvent) {
-        match self.focus {
-            Focus::Name => {
-                if ev.code != KeyCode::Up && ev.code != KeyCode::Enter {
-                    self.name_textbox.input(ev);
-                }
-
-                if ev.code == KeyCode::Down {
-                    self.focus = Focus::URL;
-                }
-            }
-            Focus::URL => {
-                self.url_textbox.input(ev);
-
-                if ev.code == KeyCode::Up {
-                    self.focus = Focus::Name;
-                }
-
-                if ev.code == KeyCode::Down {
-                    self.focus = Focus::OkButton;
-                }
-            }
-            Focus::OkButton => {
-                if ev.code == KeyCode::Up {
-                    self.focus = Focus::URL;
-                }
-                if ev.code == KeyCode::Enter {
-                    // apply
-                    self.save_endpoint = true;
-                }
-            }
-        }
-    }
-
-    pub fn centered_rect(&self, percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-        let popup_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(
-                [
-                    Constraint::Percentage((100 - percent_y) / 2),
-                    Constraint::Percentage(percent_y),
-                    Constraint::Percentage((100 - percent_y) / 2),
-                ]
-                .as_ref(),
-            )
-            .split(r);
-
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(
-                [
-                    Constraint::Percentage((100 - percent_x) / 2),
-                    Constraint::Percentage(percent_x),
-                    Constraint::Percentage((100 - percent_x) / 2),
-                ]
-                .as_ref(),
-            )
-            .split(popup_layout[1])[1]
-    }
 }
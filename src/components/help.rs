@@ -0,0 +1,169 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::components::{folder_popup::centered_rect, Action, Component};
+use crate::keymap::{HelpSection, Keymap, Scope, Shortcut};
+use crate::theme::Theme;
+
+// `?` popup listing the keys that work where the focus is, built from the
+// same keymap the command bar hints come from
+pub struct HelpOverlay {
+    // Only there while the help is open
+    sections: Option<Vec<HelpSection>>,
+    scroll: usize,
+
+    // Lines that fit in the popup, as of the last draw
+    height: usize,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+}
+
+impl HelpOverlay {
+    pub fn new(keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        Self {
+            sections: None,
+            scroll: 0,
+            height: 0,
+            keymap,
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, sections: Vec<HelpSection>) {
+        self.sections = Some(sections);
+        self.scroll = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.sections = None;
+    }
+
+    fn lines(&self) -> Vec<Spans<'static>> {
+        let sections = match &self.sections {
+            Some(sections) => sections,
+            None => return Vec::new(),
+        };
+
+        let width = sections
+            .iter()
+            .flat_map(|section| section.keys.iter())
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+
+        for section in sections.iter().filter(|section| !section.keys.is_empty()) {
+            if !lines.is_empty() {
+                lines.push(Spans::default());
+            }
+
+            lines.push(Spans::from(Span::styled(
+                section.title,
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+
+            for (keys, description) in section.keys.iter() {
+                lines.push(Spans::from(vec![
+                    Span::styled(
+                        format!("  {:width$}", keys, width = width),
+                        self.theme.fg(self.theme.label),
+                    ),
+                    Span::raw(format!("  {}", description)),
+                ]));
+            }
+        }
+
+        lines
+    }
+}
+
+impl Component for HelpOverlay {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        let last = self.lines().len().saturating_sub(self.height);
+        let page = self.height.max(1);
+
+        match self.keymap.lookup(ev, Scope::Navigation) {
+            Some(Shortcut::Down) => self.scroll = (self.scroll + 1).min(last),
+            Some(Shortcut::Up) => self.scroll = self.scroll.saturating_sub(1),
+            Some(Shortcut::PageDown) => self.scroll = (self.scroll + page).min(last),
+            Some(Shortcut::PageUp) => self.scroll = self.scroll.saturating_sub(page),
+            Some(Shortcut::Top) => self.scroll = 0,
+            Some(Shortcut::Bottom) => self.scroll = last,
+            _ => {
+                let help = self.keymap.lookup(ev, Scope::Global) == Some(Shortcut::Help);
+
+                if help || matches!(ev.code, KeyCode::Esc | KeyCode::Enter) {
+                    self.close();
+                }
+            }
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        if self.sections.is_none() {
+            return;
+        }
+
+        let area = centered_rect(60, 80, r);
+        let block = Block::default().borders(Borders::ALL).title("Help [esc]");
+
+        self.height = block.inner(area).height as usize;
+
+        let lines = self.lines();
+        self.scroll = self.scroll.min(lines.len().saturating_sub(self.height));
+
+        let help = Paragraph::new(lines)
+            .block(block)
+            .scroll((self.scroll as u16, 0));
+
+        f.render_widget(Clear, area);
+        f.render_widget(help, area);
+    }
+
+    fn is_editing(&self) -> bool {
+        self.sections.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_help_overlay() {
+        let keymap = Arc::new(Keymap::default());
+        let mut help = HelpOverlay::new(keymap.clone(), Arc::default());
+
+        help.open(keymap.help(Some(Scope::Cookies)));
+        assert!(help.is_editing());
+
+        // Titles of the three sections, a blank line between them
+        let lines = help.lines();
+        let keys = keymap.help(Some(Scope::Cookies));
+        let count = keys.iter().map(|s| s.keys.len() + 1).sum::<usize>() + 2;
+        assert_eq!(lines.len(), count);
+        assert_eq!(lines[0].0[0].content, "Global");
+
+        help.height = 5;
+        help.event(KeyEvent::from(KeyCode::End));
+        assert_eq!(help.scroll, count - 5);
+
+        // Still open on keys it doesn't use, closed by the help key itself
+        help.event(KeyEvent::from(KeyCode::Char('x')));
+        assert!(help.is_editing());
+        help.event(KeyEvent::from(KeyCode::Char('?')));
+        assert!(!help.is_editing());
+    }
+}
@@ -1,159 +1,411 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use serde_json::{Map, Value};
-use std::path::Path;
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame,
 };
+use tui_textarea::{CursorMove, TextArea};
 
-use crate::components::CommandType;
-use crate::foldertree::FolderTree;
+use crate::components::{
+    palette::highlighted, spinner::spinner, Action, CommandType, Component, Toast,
+};
+use crate::error::Result;
+use crate::foldertree::{Defaults, EndpointEntry, FolderTree};
+use crate::keymap::{Keymap, Scope, Shortcut};
+use crate::theme::Theme;
 
 pub struct ListComponent {
     list_tree: StatefulList,
     focused: bool,
+
+    // Endpoints with a request in flight and how long it has been running
+    pub pending: HashMap<String, Duration>,
+
+    // Filter being typed, only there while the filter bar is open
+    filter_box: Option<TextArea<'static>>,
+
+    // Rows that fit in the list, as of the last draw
+    height: usize,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
 }
 
 pub struct StatefulList {
     state: ListState,
     tree: FolderTree,
+
+    // Only matching endpoints are listed while it's not empty
+    filter: String,
 }
 
 // Move to another file
 impl StatefulList {
-    fn from_path<P: AsRef<Path>>(path: P) -> StatefulList {
-        let mut new_state = ListState::default();
-        let tree = FolderTree::new(path).unwrap();
+    fn from_path<P: AsRef<Path>>(path: P) -> Result<StatefulList> {
+        let tree = FolderTree::new(path)?;
+        tree.parse_all()?;
+
+        Ok(Self::from_tree(tree))
+    }
 
+    fn from_tree(tree: FolderTree) -> StatefulList {
+        let mut new_state = ListState::default();
         new_state.select(Some(0));
-        tree.parse_all();
 
         StatefulList {
             state: new_state,
             tree,
+            filter: String::new(),
         }
     }
 
     fn next(&mut self) {
+        let len = self.tree.items.borrow().len();
+
         let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.tree.items.borrow().len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+            Some(i) if i + 1 < len => i + 1,
+            _ => 0,
         };
         self.state.select(Some(i));
     }
 
     fn previous(&mut self) {
+        let len = self.tree.items.borrow().len();
+
         let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.tree.items.borrow().len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+            Some(i) if i > 0 => i - 1,
+            _ => len.saturating_sub(1),
         };
         self.state.select(Some(i));
     }
 
+    // Moves by that many rows without wrapping around
+    fn move_by(&mut self, delta: isize) {
+        let last = self.tree.items.borrow().len().saturating_sub(1) as isize;
+        let i = (self.state.selected().unwrap_or(0) as isize).saturating_add(delta);
+
+        self.state.select(Some(i.clamp(0, last) as usize));
+    }
+
+    pub fn get_current_path(&self) -> Option<String> {
+        let i = self.state.selected()?;
+        let items = self.tree.items.borrow();
+
+        items.get(i).map(|item| item.obj_ref.clone())
+    }
+
     pub fn get_current_endpoint(&self) -> Option<Map<String, Value>> {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+        let current = self.get_current_path()?;
+
+        self.tree.current_endpoint(&current)
+    }
 
-            return self.tree.current_endpoint(current);
+    pub fn get_current_defaults(&self) -> Defaults {
+        match self.get_current_path() {
+            Some(current) => self.tree.inherited_defaults(&current),
+            None => Defaults::default(),
         }
+    }
 
-        None
+    // Folders stay open while the list is filtered
+    pub fn can_unfold_folder(&self) -> bool {
+        self.filter.is_empty()
+            && self
+                .get_current_path()
+                .is_some_and(|current| self.tree.can_unfold_folder(&current))
     }
 
-    fn can_fold_folder(&self) -> bool {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+    fn fold_folder(&mut self) -> Result<()> {
+        match self.get_current_path() {
+            Some(current) if self.filter.is_empty() => self.tree.fold_folder(&current),
+            _ => Ok(()),
+        }
+    }
 
-            return self.tree.can_fold_folder(current);
+    fn unfold_folder(&mut self) -> Result<()> {
+        match self.get_current_path() {
+            Some(current) if self.filter.is_empty() => self.tree.unfold_folder(&current),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_method(&mut self, method: &str) -> Result<()> {
+        let current = self.get_current_path();
+
+        if let Some(current) = &current {
+            self.tree.set_method(current, method)?;
         }
 
-        false
+        self.refilter(current)
     }
 
-    pub fn can_unfold_folder(&self) -> bool {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+    pub fn set_body(&mut self, body: &str) -> Result<()> {
+        let current = self.get_current_path();
 
-            return self.tree.can_unfold_folder(current);
+        if let Some(current) = &current {
+            self.tree.set_body(current, body)?;
         }
 
-        false
+        self.refilter(current)
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
     }
 
-    fn fold_folder(&mut self) {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+    // Narrows the list down to the endpoints matching `query`, selecting the
+    // first good match. An empty query shows the whole tree again, still on the item
+    // that was selected.
+    pub fn set_filter(&mut self, query: &str) -> Result<()> {
+        let current = self.get_current_path();
+        self.filter = String::from(query);
 
-            self.tree.fold_folder(current);
+        if self.filter.is_empty() {
+            self.tree.parse_all()?;
+
+            return match current {
+                Some(current) => self.select_path(&current),
+                None => Ok(()),
+            };
+        }
+
+        self.tree.parse_filtered(&self.filter)?;
+
+        // URLs match loosely, so what matched by name comes first
+        let items = self.tree.items.borrow();
+        let first = items
+            .iter()
+            .position(|item| !item.highlight.is_empty())
+            .or_else(|| items.iter().position(|item| item.r#type == "endpoint"));
+        self.state.select(Some(first.unwrap_or(0)));
+
+        Ok(())
+    }
+
+    // Changing the collection lists the whole tree, this goes back to the
+    // filtered list, staying on `current` if it's still in it. It has to be
+    // taken before the change, the selection points into the filtered list
+    fn refilter(&mut self, current: Option<String>) -> Result<()> {
+        if self.filter.is_empty() {
+            return Ok(());
         }
+
+        self.tree.parse_filtered(&self.filter)?;
+
+        let items = self.tree.items.borrow();
+        let i = items
+            .iter()
+            .position(|item| Some(&item.obj_ref) == current.as_ref())
+            .unwrap_or(0);
+        self.state.select(Some(i));
+
+        Ok(())
     }
 
-    fn unfold_folder(&mut self) {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+    // Picks the collection up from disk again, staying on the same item if it's
+    // still there
+    pub fn reload(&mut self) -> Result<()> {
+        let current = self.get_current_path();
 
-            self.tree.unfold_folder(current);
+        self.tree.reload()?;
+        if !self.filter.is_empty() {
+            self.tree.parse_filtered(&self.filter)?;
         }
+
+        let items = self.tree.items.borrow();
+        let i = items
+            .iter()
+            .position(|item| Some(&item.obj_ref) == current.as_ref())
+            .or_else(|| self.state.selected())
+            .map(|i| i.min(items.len().saturating_sub(1)));
+
+        self.state.select(i);
+
+        Ok(())
     }
 
-    pub fn insert_endpoint(&mut self) {
-        if let Some(i) = self.state.selected() {
-            let items = self.tree.items.borrow().clone();
-            let current = items.get(i).unwrap().obj_ref.as_str();
+    // Selects the item, unfolding whatever it's in. The filter goes away as
+    // the item may not match it.
+    pub fn select_path(&mut self, path: &str) -> Result<()> {
+        self.filter.clear();
+        self.tree.reveal(path)?;
+
+        let i = self
+            .tree
+            .items
+            .borrow()
+            .iter()
+            .position(|item| item.obj_ref == path);
+
+        if i.is_some() {
+            self.state.select(i);
+        }
 
-            self.tree.insert_endpoint(current, "Hehe");
+        Ok(())
+    }
+
+    pub fn endpoints(&self) -> Vec<EndpointEntry> {
+        self.tree.endpoints()
+    }
+
+    pub fn insert_endpoint(&mut self) -> Result<()> {
+        let current = self.get_current_path();
+
+        if let Some(current) = &current {
+            self.tree.insert_endpoint(current, "Hehe")?;
         }
+
+        self.refilter(current)
     }
 }
 
 impl ListComponent {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+    pub fn new<P: AsRef<Path>>(path: P, keymap: Arc<Keymap>, theme: Arc<Theme>) -> Result<Self> {
+        Ok(Self::with_list(
+            StatefulList::from_path(path)?,
+            keymap,
+            theme,
+        ))
+    }
+
+    // Shown when the collection can't be loaded, it fills up once the file is
+    // fixed and reloaded
+    pub fn empty<P: AsRef<Path>>(path: P, keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        Self::with_list(
+            StatefulList::from_tree(FolderTree::empty(path)),
+            keymap,
+            theme,
+        )
+    }
+
+    fn with_list(list_tree: StatefulList, keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
         Self {
-            list_tree: StatefulList::from_path(path),
+            list_tree,
             focused: false,
+            pending: HashMap::new(),
+            filter_box: None,
+            height: 0,
+            keymap,
+            theme,
         }
     }
 
-    pub fn event(&mut self, ev: KeyEvent) {
-        match ev.code {
+    pub fn tree(&mut self) -> &mut StatefulList {
+        &mut self.list_tree
+    }
+}
+
+impl ListComponent {
+    // Up and Down still move through the list while typing
+    fn filter_event(&mut self, ev: KeyEvent) -> Action {
+        let input = match &mut self.filter_box {
+            Some(input) => input,
+            None => return Action::None,
+        };
+
+        let result = match ev.code {
+            KeyCode::Enter => {
+                self.filter_box = None;
+                return Action::None;
+            }
+            KeyCode::Esc => {
+                self.filter_box = None;
+                self.list_tree.set_filter("")
+            }
             KeyCode::Down => {
                 self.list_tree.next();
+                Ok(())
             }
             KeyCode::Up => {
                 self.list_tree.previous();
+                Ok(())
             }
-            KeyCode::Left => {
-                self.list_tree.fold_folder();
+            _ if input.input(ev) => {
+                let query = input.lines()[0].clone();
+                self.list_tree.set_filter(&query)
             }
-            KeyCode::Right => {
-                self.list_tree.unfold_folder();
+            _ => return Action::None,
+        };
+
+        match result {
+            Ok(()) => Action::SelectionChanged,
+            Err(e) => Action::Toast(Toast::from(e)),
+        }
+    }
+
+    fn open_filter(&mut self) {
+        let mut input = TextArea::from([self.list_tree.filter().to_string()]);
+        input.move_cursor(CursorMove::End);
+        input.set_cursor_line_style(Style::default());
+
+        self.filter_box = Some(input);
+    }
+}
+
+impl Component for ListComponent {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if self.filter_box.is_some() {
+            return self.filter_event(ev);
+        }
+
+        if ev.code == KeyCode::Esc && !self.list_tree.filter().is_empty() {
+            return match self.list_tree.set_filter("") {
+                Ok(()) => Action::SelectionChanged,
+                Err(e) => Action::Toast(Toast::from(e)),
+            };
+        }
+
+        let result = match self.keymap.lookup(ev, Scope::Tree) {
+            Some(Shortcut::Down) => {
+                self.list_tree.next();
+                Ok(())
+            }
+            Some(Shortcut::Up) => {
+                self.list_tree.previous();
+                Ok(())
+            }
+            Some(Shortcut::PageDown) => {
+                self.list_tree.move_by(self.height.max(1) as isize);
+                Ok(())
+            }
+            Some(Shortcut::PageUp) => {
+                self.list_tree.move_by(-(self.height.max(1) as isize));
+                Ok(())
             }
-            _ => {}
+            Some(Shortcut::Top) => {
+                self.list_tree.move_by(isize::MIN);
+                Ok(())
+            }
+            Some(Shortcut::Bottom) => {
+                self.list_tree.move_by(isize::MAX);
+                Ok(())
+            }
+            Some(Shortcut::Collapse) => self.list_tree.fold_folder(),
+            Some(Shortcut::Expand) if self.list_tree.can_unfold_folder() => {
+                self.list_tree.unfold_folder()
+            }
+            Some(Shortcut::Expand) => return Action::FocusMainPane,
+            Some(Shortcut::AddEndpoint) => return Action::OpenAddEndpoint,
+            Some(Shortcut::Method) => return Action::OpenMethodPicker,
+            Some(Shortcut::FilterTree) => {
+                self.open_filter();
+                return Action::None;
+            }
+            _ => return Action::None,
         };
+
+        match result {
+            Ok(()) => Action::SelectionChanged,
+            Err(e) => Action::Toast(Toast::from(e)),
+        }
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
         let mut items: Vec<ListItem> = Vec::new();
 
         for item in self.list_tree.tree.items.borrow().iter() {
@@ -163,21 +415,42 @@ impl ListComponent {
                 _ => Style::default(),
             };
 
-            let split_item = item.rep.split_inclusive(" ").collect::<Vec<&str>>();
+            let split_item = item.rep.split_inclusive(' ').collect::<Vec<&str>>();
 
-            let inside = split_item
-                .iter()
-                .map(|item| match *item {
-                    // This is a bit ugly, but okay
-                    "POST " => Span::styled(String::from(*item), Style::default().fg(Color::Green)),
-                    "GET " => {
-                        Span::styled(String::from(*item), Style::default().fg(Color::LightYellow))
-                    }
-                    "PUT " => Span::styled(String::from(*item), Style::default().fg(Color::Blue)),
-                    "DELETE " => Span::styled(String::from(*item), Style::default().fg(Color::Red)),
-                    _ => Span::styled(String::from(*item), Style::default()),
-                })
-                .collect::<Vec<Span>>();
+            // Endpoints are rendered as "<indent> METHOD name", so the method is
+            // the first word and custom verbs get colored too
+            let method_idx = match item.r#type.as_str() {
+                "endpoint" => split_item.iter().position(|s| !s.trim().is_empty()),
+                _ => None,
+            };
+
+            // Characters the filter matched are highlighted word by word
+            let mut offset = 0;
+            let mut inside = Vec::new();
+
+            for (i, s) in split_item.iter().enumerate() {
+                let style = match Some(i) == method_idx {
+                    true => self.theme.fg(self.theme.method(s)),
+                    false => Style::default(),
+                };
+                let len = s.chars().count();
+                let positions = item
+                    .highlight
+                    .iter()
+                    .filter(|p| (offset..offset + len).contains(*p))
+                    .map(|p| p - offset)
+                    .collect::<Vec<usize>>();
+
+                inside.append(&mut highlighted(s, &positions, style, &self.theme));
+                offset += len;
+            }
+
+            if let Some(elapsed) = self.pending.get(&item.obj_ref) {
+                inside.push(Span::styled(
+                    format!(" {}", spinner(*elapsed)),
+                    self.theme.fg(self.theme.warning),
+                ));
+            }
 
             items.push(ListItem::new(vec![Spans::from(inside)]).style(style))
         }
@@ -188,43 +461,158 @@ impl ListComponent {
         };
 
         let highlight_style = if self.focused {
-            Style::default()
-                .bg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD)
+            self.theme.selected().add_modifier(Modifier::BOLD)
         } else {
             Style::default().add_modifier(Modifier::BOLD)
         };
 
+        // The filter sits in the bottom border of the list
+        let filter = match &self.filter_box {
+            Some(_) => Some(Spans::from("/")),
+            None if !self.list_tree.filter().is_empty() => Some(Spans::from(vec![
+                Span::raw("/"),
+                Span::styled(
+                    self.list_tree.filter().to_string(),
+                    self.theme.fg(self.theme.highlight),
+                ),
+            ])),
+            None => None,
+        };
+
         let the_list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .border_type(border_type),
+                    .border_type(border_type)
+                    .border_style(self.theme.border(self.focused)),
             )
             .highlight_style(highlight_style);
 
+        self.height = r.height.saturating_sub(2) as usize;
         f.render_stateful_widget(the_list, r, &mut self.list_tree.state);
+
+        if let Some(filter) = filter {
+            let bar = Rect::new(
+                r.x + 1,
+                r.bottom().saturating_sub(1),
+                r.width.saturating_sub(2),
+                1,
+            );
+            f.render_widget(Clear, bar);
+            f.render_widget(Paragraph::new(filter), bar);
+
+            if let Some(input) = &self.filter_box {
+                let input_area = Rect::new(bar.x + 1, bar.y, bar.width.saturating_sub(1), 1);
+                f.render_widget(input.widget(), input_area);
+            }
+        }
     }
 
-    pub fn generate_cmds(&self) -> Vec<CommandType> {
+    fn commands(&self) -> Vec<CommandType> {
+        if self.filter_box.is_some() {
+            return vec![
+                CommandType::Command(String::from("Done [⏎]")),
+                CommandType::Break,
+                CommandType::Command(String::from("Clear [Esc]")),
+            ];
+        }
+
+        let keymap = &self.keymap;
+
         vec![
-            CommandType::Command(String::from("Add [a]")),
+            keymap.hint("Add", &[Shortcut::AddEndpoint]),
+            CommandType::Break,
+            keymap.hint("Method", &[Shortcut::Method]),
+            CommandType::Break,
+            keymap.hint("Filter", &[Shortcut::FilterTree]),
+            CommandType::Break,
+            keymap.hint("Tabs", &Shortcut::TABS),
             CommandType::Break,
-            CommandType::Command(String::from("Tabs [1-2]")),
+            keymap.hint("Palette", &[Shortcut::Palette]),
             CommandType::Break,
-            CommandType::Command(String::from("Switch pane [←→]")),
+            keymap.hint("Help", &[Shortcut::Help]),
+            CommandType::Break,
+            keymap.hint("Fold", &[Shortcut::Collapse, Shortcut::Expand]),
+            CommandType::Break,
+            keymap.hint("Switch pane", &[Shortcut::Expand]),
         ]
     }
 
-    pub fn tree(&mut self) -> &mut StatefulList {
-        &mut self.list_tree
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Tree)
     }
 
-    pub fn focused(&self) -> bool {
-        self.focused
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
     }
 
-    pub fn set_focus(&mut self, val: bool) {
-        self.focused = val;
+    fn is_editing(&self) -> bool {
+        self.filter_box.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_edit_while_filtered() {
+        let input = r#"
+            {
+                "root": [
+                    {
+                        "type": "folder",
+                        "name": "Shop",
+                        "folded": false,
+                        "path": "/root/0",
+                        "items": [
+                            {
+                                "type": "endpoint",
+                                "name": "List users",
+                                "method": "GET",
+                                "path": "/root/0/items/0",
+                                "url": "http://localhost:3000/users"
+                            },
+                            {
+                                "type": "endpoint",
+                                "name": "Add order",
+                                "method": "POST",
+                                "path": "/root/0/items/1",
+                                "url": "http://localhost:3000/orders"
+                            },
+                            {
+                                "type": "endpoint",
+                                "name": "Add user",
+                                "method": "POST",
+                                "path": "/root/0/items/2",
+                                "url": "http://localhost:3000/users"
+                            }
+                        ]
+                    }
+                ]
+            }
+        "#;
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(input.as_bytes()).unwrap();
+
+        let mut list = StatefulList::from_path(file.path()).unwrap();
+        list.set_filter("add user").unwrap();
+        assert_eq!(list.get_current_path().as_deref(), Some("/root/0/items/2"));
+
+        list.set_method("PUT").unwrap();
+        assert_eq!(list.get_current_path().as_deref(), Some("/root/0/items/2"));
+
+        list.set_body("{}").unwrap();
+        assert_eq!(list.get_current_path().as_deref(), Some("/root/0/items/2"));
+
+        list.insert_endpoint().unwrap();
+        assert_eq!(list.get_current_path().as_deref(), Some("/root/0/items/2"));
+
+        let endpoint = list.get_current_endpoint().unwrap();
+        assert_eq!(endpoint["method"], "PUT");
+        assert_eq!(endpoint["json_body"], "{}");
     }
 }
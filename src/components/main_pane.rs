@@ -1,35 +1,295 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use regex::Regex;
 use serde_json::{Map, Value};
+use std::{sync::Arc, time::Duration};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Span, Spans},
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
 use tui_textarea::TextArea;
 
-use crate::components::CommandType;
+use crate::components::{
+    format::{format_elapsed, format_size},
+    search_bar::{SearchBar, SearchEvent, SearchTarget},
+    spinner::spinner,
+    Action, CommandType, Component, ResponseViewer,
+};
+use crate::foldertree::Defaults;
+use crate::http::{
+    variables::{resolve_url, substitute},
+    HistoryEntry, Metrics,
+};
+use crate::keymap::{Keymap, Scope, Shortcut};
+use crate::theme::Theme;
+
+// Width of the name and duration columns next to the waterfall bars
+const WATERFALL_LABELS: u16 = 20;
 
 pub struct MainPaneComponent<'a> {
-    pub focused: bool,
+    focused: bool,
     pub current_endpoint: Map<String, Value>,
+    pub current_defaults: Defaults,
     pub body_textbox: TextArea<'a>,
+
+    // How long the request of the current endpoint has been running, if there is one
+    pub pending: Option<Duration>,
+
+    // Most recent request sent from the current endpoint
+    pub last_request: Option<HistoryEntry>,
+
+    pub viewer: ResponseViewer<'a>,
+
+    // Shows the timing waterfall instead of the body
+    pub show_timings: bool,
+
+    // Searches either the response or the request body
+    search: SearchBar<'a>,
+
+    // Keys go into the body until Esc saves it
+    editing_body: bool,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
 }
 
 impl MainPaneComponent<'_> {
-    pub fn new() -> Self {
+    pub fn new(keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
         Self {
             focused: true,
             current_endpoint: Map::new(),
+            current_defaults: Defaults::default(),
             body_textbox: TextArea::default(),
+            pending: None,
+            last_request: None,
+            viewer: ResponseViewer::new(keymap.clone(), theme.clone()),
+            show_timings: false,
+            search: SearchBar::new(keymap.clone(), theme.clone()),
+            editing_body: false,
+            keymap,
+            theme,
+        }
+    }
+
+    // Highlights the matches in the searched target and jumps to the first one
+    // from where the cursor is, so the view follows the query while typing
+    fn apply_search(&mut self) {
+        let pattern = self.search.pattern();
+
+        match self.search.target {
+            SearchTarget::Response => {
+                let _ = self.body_textbox.set_search_pattern("");
+                self.viewer.set_search(pattern);
+                self.viewer.next_match(true, true);
+            }
+            SearchTarget::Body => {
+                self.viewer.set_search(None);
+
+                let pattern = pattern.as_ref().map(Regex::as_str).unwrap_or_default();
+                if self.body_textbox.set_search_pattern(pattern).is_ok() {
+                    self.body_textbox.search_forward(true);
+                }
+            }
+        }
+    }
+
+    fn find(&mut self, forward: bool) {
+        match self.search.target {
+            SearchTarget::Response => {
+                self.viewer.next_match(forward, false);
+            }
+            SearchTarget::Body if forward => {
+                self.body_textbox.search_forward(false);
+            }
+            SearchTarget::Body => {
+                self.body_textbox.search_back(false);
+            }
         }
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+    fn match_status(&self) -> String {
+        match self.search.target {
+            SearchTarget::Response => self.viewer.match_status(),
+            SearchTarget::Body => {
+                let count = self.body_textbox.search_pattern().map_or(0, |pattern| {
+                    self.body_textbox
+                        .lines()
+                        .iter()
+                        .map(|line| pattern.find_iter(line).count())
+                        .sum()
+                });
+
+                match count {
+                    0 => String::from("no matches"),
+                    count => format!("{} matches", count),
+                }
+            }
+        }
+    }
+
+    fn request_status(&self) -> Spans<'_> {
+        if let Some(elapsed) = self.pending {
+            let cancel = match self.keymap.keys(&[Shortcut::Cancel]) {
+                keys if keys.is_empty() => String::new(),
+                keys => format!(" (cancel with {})", keys),
+            };
+
+            return Spans::from(vec![
+                Span::styled(spinner(elapsed), self.theme.fg(self.theme.warning)),
+                Span::raw(format!(" Sending... {}{}", format_elapsed(elapsed), cancel)),
+            ]);
+        }
+
+        let entry = match &self.last_request {
+            Some(entry) => entry,
+            None => return Spans::from(""),
+        };
+
+        let color = self.theme.outcome(&entry.outcome);
+        let elapsed = entry.elapsed.map(format_elapsed).unwrap_or_default();
+
+        let content_type = entry
+            .response
+            .as_ref()
+            .and_then(|r| r.content_type())
+            .map(|value| format!(" · {}", value))
+            .unwrap_or_default();
+
+        Spans::from(vec![
+            Span::styled(entry.outcome.to_string(), self.theme.fg(color)),
+            Span::raw(format!(" after {}{}", elapsed, content_type)),
+        ])
+    }
+
+    // Method and URL of the current endpoint followed by everything it
+    // inherits from its folders, the way it will actually be sent
+    fn effective_values(&self) -> Vec<String> {
+        if self.current_endpoint.is_empty() {
+            return vec![String::new()];
+        }
+
+        let defaults = &self.current_defaults;
+        let vars = &defaults.variables;
+        let get = |key: &str| {
+            self.current_endpoint
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+        };
+
+        let base_url = defaults.base_url.as_ref().map(|b| substitute(b, vars));
+        let url = resolve_url(&substitute(get("url"), vars), base_url.as_deref());
+
+        let mut lines = vec![format!("{} | {}", get("method"), url)];
+
+        if let Some(environment) = &defaults.environment {
+            lines.push(format!("Environment: {}", environment));
+        }
+
+        if let Some(proxy) = &defaults.settings.proxy {
+            if let Some(url) = proxy
+                .url
+                .as_ref()
+                .or(proxy.https.as_ref())
+                .or(proxy.http.as_ref())
+            {
+                lines.push(format!("Proxy: {}", url));
+            }
+        }
+
+        if let Some((scope, auth)) = &defaults.auth {
+            lines.push(format!("Auth: {} (from {})", auth.name(), scope));
+        }
+
+        for (name, value) in defaults.headers.iter() {
+            lines.push(format!("{}: {}", name, substitute(value, vars)));
+        }
+
+        if !vars.is_empty() {
+            let vars = vars
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            lines.push(format!("Variables: {}", vars));
+        }
+
+        lines
+    }
+}
+
+impl Component for MainPaneComponent<'_> {
+    // Typing into the body, the search bar or one of the viewer's prompts
+    fn is_editing(&self) -> bool {
+        self.editing_body || self.search.is_open() || self.viewer.is_editing()
+    }
+
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if self.editing_body {
+            if ev.code == KeyCode::Esc {
+                self.editing_body = false;
+                return Action::SetBody(self.body_textbox.lines().join("\n"));
+            }
+
+            self.body_textbox.input(ev);
+            return Action::None;
+        }
+
+        if self.search.is_open() {
+            if self.search.event(ev) != SearchEvent::Ignored {
+                self.apply_search();
+            }
+            return Action::None;
+        }
+
+        if self.viewer.is_editing() {
+            self.viewer.event(ev);
+            return Action::None;
+        }
+
+        if ev.code == KeyCode::Esc && self.search.is_visible() {
+            self.search.clear();
+            self.apply_search();
+            return Action::None;
+        }
+
+        match self.keymap.lookup(ev, Scope::Response) {
+            Some(Shortcut::FocusTree) => return Action::FocusTree,
+            // Like in the tree, folding with nothing left to fold moves on
+            Some(Shortcut::FoldValue) if !self.viewer.set_folded(true) => return Action::FocusTree,
+            Some(Shortcut::FoldValue) => {}
+            Some(Shortcut::UnfoldValue) => {
+                self.viewer.set_folded(false);
+            }
+            Some(Shortcut::Send) => return Action::SendRequest,
+            Some(Shortcut::Cancel) => return Action::CancelRequest,
+            Some(Shortcut::Method) => return Action::OpenMethodPicker,
+            Some(Shortcut::Search) => self.search.open(),
+            Some(Shortcut::NextMatch) => self.find(true),
+            Some(Shortcut::PrevMatch) => self.find(false),
+            Some(Shortcut::Timings) => self.show_timings = !self.show_timings,
+            Some(Shortcut::EditBody) if !self.current_endpoint.is_empty() => {
+                self.editing_body = true;
+            }
+            _ => self.viewer.event(ev),
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        let summary = self.effective_values();
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(10), Constraint::Percentage(90)])
+            .constraints([
+                Constraint::Length(summary.len() as u16 + 2),
+                Constraint::Min(0),
+            ])
             .split(r);
 
         let lower_inner = chunks[1].inner(&Margin {
@@ -37,6 +297,13 @@ impl MainPaneComponent<'_> {
             horizontal: 1,
         });
 
+        let search_height = if self.search.is_visible() { 1 } else { 0 };
+        let lower = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(search_height)])
+            .split(lower_inner);
+        let lower_inner = lower[0];
+
         let inside = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -47,39 +314,62 @@ impl MainPaneComponent<'_> {
             false => BorderType::Plain,
         };
 
-        let mut curr = String::new();
-        let mut body: Vec<&str> = vec![""];
-
-        if !self.current_endpoint.is_empty() {
-            curr = format!(
-                "{} | {}",
-                self.current_endpoint["method"], self.current_endpoint["url"]
-            );
-            body = self.current_endpoint["json_body"]
-                .as_str()
-                .unwrap()
-                .lines()
-                .collect();
-        }
+        let mut body: Vec<&str> = self
+            .current_endpoint
+            .get("json_body")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .lines()
+            .collect();
 
-        let tmp_vect = vec![curr.as_str()];
-        let spans_inside = tmp_vect
-            .iter()
-            .map(|item| Span::raw(item.replace("\"", "")))
-            .collect::<Vec<Span>>();
+        // An empty text area still has its one empty line
+        if body.is_empty() {
+            body.push("");
+        }
 
-        let text = Spans::from(spans_inside);
+        let text = summary
+            .into_iter()
+            .map(|line| Spans::from(Span::raw(line)))
+            .collect::<Vec<Spans>>();
 
         let temp_block = Paragraph::new(text).block(Block::default().borders(Borders::ALL));
 
-        let temp_block2 = Block::default()
+        let mut temp_block2 = Block::default()
             .borders(Borders::ALL)
-            .border_type(border_type);
+            .border_type(border_type)
+            .border_style(self.theme.border(self.focused));
 
-        self.body_textbox = TextArea::from(body);
+        if self.editing_body {
+            temp_block2 = temp_block2.title("-- INSERT --");
+        }
+
+        // Only replaced when the body changes so the search position is kept,
+        // and never while it's being edited
+        let changed = !self.editing_body
+            && self
+                .body_textbox
+                .lines()
+                .iter()
+                .map(String::as_str)
+                .ne(body.iter().copied());
+
+        if changed {
+            self.body_textbox = TextArea::from(body);
+
+            self.body_textbox
+                .set_search_style(self.theme.search_match(false));
+
+            if self.search.target == SearchTarget::Body {
+                self.apply_search();
+            }
+        }
 
-        self.body_textbox
-            .set_cursor_style(Style::default().bg(Color::Black));
+        // The cursor only shows while typing
+        let cursor = match self.editing_body {
+            true => Style::default().add_modifier(Modifier::REVERSED),
+            false => Style::default(),
+        };
+        self.body_textbox.set_cursor_style(cursor);
 
         let body_widget = self.body_textbox.widget();
 
@@ -87,13 +377,217 @@ impl MainPaneComponent<'_> {
         f.render_widget(temp_block2, chunks[1]);
 
         f.render_widget(body_widget, inside[0]);
+
+        let response = self.last_request.as_ref().and_then(|e| e.response.clone());
+        let endpoint = self
+            .last_request
+            .as_ref()
+            .map(|e| e.endpoint.clone())
+            .unwrap_or_default();
+        self.viewer.set_response(&endpoint, response.clone());
+
+        let response_block = Block::default().borders(Borders::TOP).title("Response");
+        let response_inner = response_block.inner(inside[1]);
+
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(response_inner);
+
+        f.render_widget(response_block, inside[1]);
+        f.render_widget(Paragraph::new(self.request_status()), parts[0]);
+
+        match response {
+            Some(response) if self.show_timings && self.pending.is_none() => {
+                let width = parts[1].width.saturating_sub(WATERFALL_LABELS);
+                let timings = Paragraph::new(waterfall(&response.metrics, width, &self.theme));
+                f.render_widget(timings, parts[1]);
+            }
+            Some(_) if self.pending.is_none() => self.viewer.draw(f, parts[1]),
+            _ => {}
+        }
+
+        if self.search.is_visible() {
+            let matches = self.match_status();
+            self.search.draw(f, lower[1], &matches);
+        }
     }
 
-    pub fn generate_cmds(&self) -> Vec<CommandType> {
+    fn commands(&self) -> Vec<CommandType> {
+        let keymap = &self.keymap;
+
+        if self.editing_body {
+            return vec![CommandType::Command(String::from("Done [Esc]"))];
+        }
+
+        if self.search.is_open() {
+            return vec![
+                keymap.hint("Response/body", &[Shortcut::SearchTarget]),
+                CommandType::Break,
+                keymap.hint("Case", &[Shortcut::SearchCase]),
+                CommandType::Break,
+                keymap.hint("Regex", &[Shortcut::SearchRegex]),
+                CommandType::Break,
+                CommandType::Command(String::from("Done [⏎]")),
+            ];
+        }
+
         vec![
-            CommandType::Command(String::from("main [o]")),
+            keymap.hint("Send", &[Shortcut::Send]),
+            CommandType::Break,
+            keymap.hint("Cancel", &[Shortcut::Cancel]),
+            CommandType::Break,
+            keymap.hint(
+                "Fold",
+                &[Shortcut::Fold, Shortcut::FoldValue, Shortcut::UnfoldValue],
+            ),
+            CommandType::Break,
+            keymap.hint(
+                "Search",
+                &[Shortcut::Search, Shortcut::NextMatch, Shortcut::PrevMatch],
+            ),
             CommandType::Break,
-            CommandType::Command(String::from("pane [x]")),
+            keymap.hint("Filter", &[Shortcut::Filter]),
+            CommandType::Break,
+            keymap.hint("Timings", &[Shortcut::Timings]),
+            CommandType::Break,
+            keymap.hint("Hex", &[Shortcut::Hex]),
+            CommandType::Break,
+            keymap.hint("Save body", &[Shortcut::SaveBody]),
+            CommandType::Break,
+            keymap.hint("Edit body", &[Shortcut::EditBody]),
+            CommandType::Break,
+            keymap.hint("Method", &[Shortcut::Method]),
+            CommandType::Break,
+            keymap.hint("Switch pane", &[Shortcut::FocusTree]),
         ]
     }
+
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Response)
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+}
+
+fn waterfall(metrics: &Metrics, width: u16, theme: &Theme) -> Vec<Spans<'static>> {
+    let timings = &metrics.timings;
+    let sizes = &metrics.sizes;
+
+    let total = timings.total().as_secs_f64().max(f64::EPSILON);
+    let cells = |d: Duration| (d.as_secs_f64() / total * width as f64).round() as usize;
+
+    let mut offset = 0;
+    let mut lines = vec![Spans::from("")];
+
+    for (name, duration) in timings.phases() {
+        // Every phase gets at least one cell so short ones are still visible
+        let len = cells(duration).max(1);
+
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{:<9}", name)),
+            Span::raw(" ".repeat(offset)),
+            Span::styled("█".repeat(len), theme.fg(theme.timing(name))),
+            Span::raw(format!(" {}", format_elapsed(duration))),
+        ]));
+
+        offset += cells(duration);
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(format!(
+        "Request   ~{} headers, {} body",
+        format_size(sizes.request_headers),
+        format_size(sizes.request_body)
+    )));
+
+    let mut body = format!("{} body", format_size(sizes.response_body));
+    if sizes.decoded_body != sizes.response_body {
+        body.push_str(&format!(" ({} decoded)", format_size(sizes.decoded_body)));
+    }
+
+    lines.push(Spans::from(format!(
+        "Response  ~{} headers, {}",
+        format_size(sizes.response_headers),
+        body
+    )));
+
+    if timings.dns.is_none() {
+        lines.push(Spans::from(Span::styled(
+            "No new connection was timed, it was reused or went through a proxy",
+            theme.fg(theme.muted),
+        )));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Response;
+    use tui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn test_draw_without_body() {
+        let mut pane = MainPaneComponent::new(Arc::default(), Arc::default());
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+
+        let mut endpoint = Map::new();
+        endpoint.insert(String::from("method"), Value::from("GET"));
+        endpoint.insert(String::from("url"), Value::from("http://localhost/"));
+        pane.current_endpoint = endpoint;
+
+        terminal.draw(|f| pane.draw(f, f.size())).unwrap();
+        assert_eq!(pane.body_textbox.lines(), [""]);
+    }
+
+    #[test]
+    fn test_vim_folding() {
+        let mut pane = MainPaneComponent::new(Arc::new(Keymap::vim()), Arc::default());
+        let response = Response {
+            status: reqwest::StatusCode::OK,
+            headers: vec![(
+                String::from("Content-Type"),
+                String::from("application/json"),
+            )],
+            body: bytes::Bytes::from_static(br#"{"a": [1, 2]}"#),
+            metrics: Default::default(),
+        };
+        pane.viewer
+            .set_response("/root/0", Some(Arc::new(response)));
+
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        assert_eq!(pane.event(key('h')), Action::None);
+        assert!(!pane.viewer.set_folded(true));
+
+        // Already folded, so h goes on to the tree
+        assert_eq!(pane.event(key('h')), Action::FocusTree);
+
+        assert_eq!(pane.event(key('l')), Action::None);
+        assert!(!pane.viewer.set_folded(false));
+    }
+
+    #[test]
+    fn test_cancel_hint_follows_keymap() {
+        let status = |pane: &MainPaneComponent| {
+            pane.request_status()
+                .0
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        };
+
+        let mut pane = MainPaneComponent::new(Arc::default(), Arc::default());
+        pane.pending = Some(std::time::Duration::from_millis(1500));
+        assert!(status(&pane).ends_with("(cancel with c)"));
+
+        let keymap = Keymap::from_toml("cancel = \"ctrl-x\"").unwrap();
+        let mut pane = MainPaneComponent::new(Arc::new(keymap), Arc::default());
+        pane.pending = Some(std::time::Duration::from_millis(1500));
+        assert!(status(&pane).ends_with("(cancel with ctrl-x)"));
+    }
 }
@@ -0,0 +1,375 @@
+use tui::text::Span;
+
+use crate::theme::Theme;
+
+// Elements that never have content or a closing tag in HTML
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// Elements whose content is kept as it is in HTML
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+// Text that fits next to its tags stays on their line
+const INLINE_TEXT: usize = 80;
+
+#[derive(Debug, PartialEq)]
+enum Token<'t> {
+    Open {
+        raw: &'t str,
+        name: String,
+        self_closing: bool,
+    },
+    Close {
+        raw: &'t str,
+        name: String,
+    },
+    Text(&'t str),
+    // Comments, doctypes, processing instructions and CDATA
+    Other(&'t str),
+}
+
+// Pretty-prints XML or HTML one element per line and highlights it. Broken
+// markup is tolerated, whatever can't be read as a tag is kept as text.
+pub fn markup_lines(text: &str, html: bool, theme: &Theme) -> Vec<Vec<Span<'static>>> {
+    let tokens = tokenize(text, html);
+    let mut lines = Vec::new();
+    let mut indent = 0;
+    let mut i = 0;
+
+    let pad = |indent: usize| Span::raw("  ".repeat(indent));
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Open {
+                raw,
+                name,
+                self_closing,
+            } => {
+                let void = *self_closing || (html && VOID_ELEMENTS.contains(&name.as_str()));
+
+                // <a>short text</a> stays on one line
+                if let (
+                    Some(Token::Text(text)),
+                    Some(Token::Close {
+                        raw: close,
+                        name: n,
+                    }),
+                ) = (tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    let text = text.trim();
+
+                    if !void && n == name && !text.contains('\n') && text.len() <= INLINE_TEXT {
+                        let mut line = vec![pad(indent)];
+                        line.extend(tag_spans(raw, theme));
+                        line.push(Span::raw(String::from(text)));
+                        line.extend(tag_spans(close, theme));
+                        lines.push(line);
+
+                        i += 3;
+                        continue;
+                    }
+                }
+
+                let mut line = vec![pad(indent)];
+                line.extend(tag_spans(raw, theme));
+                lines.push(line);
+
+                if !void {
+                    indent += 1;
+                }
+            }
+            Token::Close { raw, .. } => {
+                indent = indent.saturating_sub(1);
+
+                let mut line = vec![pad(indent)];
+                line.extend(tag_spans(raw, theme));
+                lines.push(line);
+            }
+            Token::Text(text) => {
+                for text_line in text.trim().lines() {
+                    let text_line = text_line.trim();
+
+                    if !text_line.is_empty() {
+                        lines.push(vec![pad(indent), Span::raw(String::from(text_line))]);
+                    }
+                }
+            }
+            Token::Other(raw) => {
+                for (n, other_line) in raw.lines().enumerate() {
+                    let other_line = if n == 0 {
+                        other_line
+                    } else {
+                        other_line.trim()
+                    };
+
+                    lines.push(vec![
+                        pad(indent),
+                        Span::styled(String::from(other_line), theme.fg(theme.json_punctuation)),
+                    ]);
+                }
+            }
+        }
+
+        i += 1;
+    }
+
+    lines
+}
+
+fn tokenize(text: &str, html: bool) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    // Start of the text before the next tag, kept open so a lone '<' doesn't
+    // split it up
+    let mut text_from = None;
+
+    while pos < text.len() {
+        let start = match text[pos..].find('<') {
+            Some(start) => pos + start,
+            None => break,
+        };
+
+        if start > pos {
+            text_from.get_or_insert(pos);
+        }
+
+        pos = start;
+        let rest = &text[pos..];
+
+        let special = [
+            ("<!--", "-->"),
+            ("<![CDATA[", "]]>"),
+            ("<!", ">"),
+            ("<?", ">"),
+        ]
+        .iter()
+        .find(|(open, _)| rest.starts_with(open));
+
+        if let Some((open, close)) = special {
+            if let Some(from) = text_from.take() {
+                tokens.push(Token::Text(&text[from..pos]));
+            }
+
+            let end = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+
+            tokens.push(Token::Other(&rest[..end]));
+            pos += end;
+            continue;
+        }
+
+        let closing = rest.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+
+        let is_tag = rest[name_start..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':');
+
+        let end = match tag_end(rest) {
+            Some(end) if is_tag => end,
+            // A lone '<' is just text
+            _ => {
+                text_from.get_or_insert(pos);
+                pos += 1;
+                continue;
+            }
+        };
+
+        if let Some(from) = text_from.take() {
+            tokens.push(Token::Text(&text[from..pos]));
+        }
+
+        let raw = &rest[..end];
+        let name = raw[name_start..]
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let name = if html {
+            name.to_ascii_lowercase()
+        } else {
+            name
+        };
+
+        pos += end;
+
+        if closing {
+            tokens.push(Token::Close { raw, name });
+            continue;
+        }
+
+        let self_closing = raw.ends_with("/>");
+        let raw_text = html && !self_closing && RAW_TEXT_ELEMENTS.contains(&name.as_str());
+
+        tokens.push(Token::Open {
+            raw,
+            name: name.clone(),
+            self_closing,
+        });
+
+        // Scripts and styles may contain anything up to their closing tag
+        if raw_text {
+            let rest = &text[pos..];
+            let close = format!("</{}", name);
+            let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+
+            if end > 0 {
+                tokens.push(Token::Text(&rest[..end]));
+            }
+            pos += end;
+        }
+    }
+
+    if let Some(from) = text_from.or(Some(pos).filter(|pos| *pos < text.len())) {
+        tokens.push(Token::Text(&text[from..]));
+    }
+
+    tokens
+}
+
+// Where the tag at the start of `text` ends, '>' inside quotes doesn't count
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// Tag names, attribute names and their values each in their own color
+fn tag_spans(raw: &str, theme: &Theme) -> Vec<Span<'static>> {
+    let bracket = theme.fg(theme.json_punctuation);
+    let mut spans = Vec::new();
+
+    let open_len = if raw.starts_with("</") { 2 } else { 1 };
+    let close_len = if raw.ends_with("/>") { 2 } else { 1 };
+    let inner = &raw[open_len..raw.len().saturating_sub(close_len).max(open_len)];
+
+    spans.push(Span::styled(String::from(&raw[..open_len]), bracket));
+
+    let name_len = inner
+        .find(|c: char| c.is_whitespace())
+        .unwrap_or(inner.len());
+    spans.push(Span::styled(
+        String::from(&inner[..name_len]),
+        theme.fg(theme.markup_tag),
+    ));
+
+    let mut rest = &inner[name_len..];
+
+    while !rest.is_empty() {
+        let space = rest.len() - rest.trim_start().len();
+        if space > 0 {
+            spans.push(Span::raw(String::from(&rest[..space])));
+            rest = &rest[space..];
+            continue;
+        }
+
+        let attr_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        spans.push(Span::styled(
+            String::from(&rest[..attr_len]),
+            theme.fg(theme.markup_attribute),
+        ));
+        rest = &rest[attr_len..];
+
+        if let Some(value) = rest.strip_prefix('=') {
+            let value_len = match value.chars().next() {
+                Some(q) if q == '"' || q == '\'' => {
+                    value[1..].find(q).map_or(value.len(), |end| end + 2)
+                }
+                _ => value
+                    .find(|c: char| c.is_whitespace())
+                    .unwrap_or(value.len()),
+            };
+
+            spans.push(Span::raw("="));
+            spans.push(Span::styled(
+                String::from(&value[..value_len]),
+                theme.fg(theme.json_string),
+            ));
+            rest = &value[value_len..];
+        }
+    }
+
+    spans.push(Span::styled(
+        String::from(&raw[raw.len() - close_len..]),
+        bracket,
+    ));
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Vec<Span>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_xml() {
+        let xml = r#"<?xml version="1.0"?><root><item id="1" name='a > b'>One</item><empty/><!-- note --></root>"#;
+
+        assert_eq!(
+            text(&markup_lines(xml, false, &Theme::default())),
+            vec![
+                "<?xml version=\"1.0\"?>",
+                "<root>",
+                "  <item id=\"1\" name='a > b'>One</item>",
+                "  <empty/>",
+                "  <!-- note -->",
+                "</root>",
+            ]
+        );
+
+        let theme = Theme::default();
+        let spans = tag_spans(r#"<item id="1">"#, &theme);
+        assert_eq!(spans[1].style.fg, Some(theme.markup_tag));
+        assert_eq!(spans[3].style.fg, Some(theme.markup_attribute));
+        assert_eq!(spans[5].content, "\"1\"");
+        assert_eq!(spans[5].style.fg, Some(theme.json_string));
+    }
+
+    #[test]
+    fn test_html() {
+        let html = "<!DOCTYPE html><HTML><head><meta charset=utf-8><script>if (a < b) {}</script></head><body><p>1 < 2<br>x</p></body></html>";
+
+        assert_eq!(
+            text(&markup_lines(html, true, &Theme::default())),
+            vec![
+                "<!DOCTYPE html>",
+                "<HTML>",
+                "  <head>",
+                "    <meta charset=utf-8>",
+                "    <script>if (a < b) {}</script>",
+                "  </head>",
+                "  <body>",
+                "    <p>",
+                "      1 < 2",
+                "      <br>",
+                "      x",
+                "    </p>",
+                "  </body>",
+                "</html>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::components::{Action, Component};
+use crate::theme::Theme;
+
+const METHODS: [&str; 9] = [
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+pub struct MethodPopup<'a> {
+    pub is_open: bool,
+    state: ListState,
+
+    // Text box for methods that aren't in the list, like PROPFIND or PURGE
+    custom: Option<TextArea<'a>>,
+
+    theme: Arc<Theme>,
+}
+
+impl MethodPopup<'_> {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            is_open: false,
+            state: ListState::default(),
+            custom: None,
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, current: &str) {
+        let current = current.to_ascii_uppercase();
+
+        // Custom methods start out in the text box so they can be edited
+        match METHODS.iter().position(|m| *m == current) {
+            Some(i) => self.state.select(Some(i)),
+            None => {
+                self.state.select(Some(METHODS.len()));
+                if !current.is_empty() {
+                    self.custom = Some(Self::custom_textbox(&current));
+                }
+            }
+        }
+
+        self.is_open = true;
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.custom = None;
+    }
+
+    fn custom_textbox<'b>(method: &str) -> TextArea<'b> {
+        let mut textbox = TextArea::from([String::from(method)]);
+        textbox.set_cursor_line_style(Style::default());
+        textbox.set_block(Self::custom_block("Custom method"));
+        textbox.move_cursor(tui_textarea::CursorMove::End);
+
+        textbox
+    }
+
+    fn custom_block<'b>(title: &'b str) -> Block<'b> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .title(title)
+    }
+}
+
+impl Component for MethodPopup<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if let Some(custom) = &mut self.custom {
+            match ev.code {
+                KeyCode::Esc => self.custom = None,
+                KeyCode::Enter => {
+                    let method = custom.lines()[0].trim().to_ascii_uppercase();
+
+                    if reqwest::Method::from_bytes(method.as_bytes()).is_ok() {
+                        return Action::SetMethod(method);
+                    } else {
+                        custom.set_block(Self::custom_block("Invalid method"));
+                    }
+                }
+                _ => {
+                    custom.input(ev);
+                }
+            }
+            return Action::None;
+        }
+
+        let selected = self.state.selected().unwrap_or(0);
+        let len = METHODS.len() + 1;
+
+        match ev.code {
+            KeyCode::Down => self.state.select(Some((selected + 1) % len)),
+            KeyCode::Up => self.state.select(Some((selected + len - 1) % len)),
+            KeyCode::Esc => return Action::CloseMethodPicker,
+            KeyCode::Enter => match METHODS.get(selected) {
+                Some(method) => return Action::SetMethod(String::from(*method)),
+                None => self.custom = Some(Self::custom_textbox("")),
+            },
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        if !self.is_open {
+            return;
+        }
+
+        let block = Block::default().borders(Borders::ALL).title("Method");
+        let inner = block.inner(r);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(inner);
+
+        let mut items = METHODS
+            .iter()
+            .map(|m| ListItem::new(Span::styled(*m, self.theme.fg(self.theme.method(m)))))
+            .collect::<Vec<ListItem>>();
+        items.push(ListItem::new("Custom..."));
+
+        let list =
+            List::new(items).highlight_style(self.theme.selected().add_modifier(Modifier::BOLD));
+
+        // We have to clear out the background first
+        f.render_widget(Clear, r);
+        f.render_widget(block, r);
+        f.render_stateful_widget(list, layout[0], &mut self.state);
+
+        if let Some(custom) = &self.custom {
+            f.render_widget(custom.widget(), layout[1]);
+        }
+    }
+
+    fn is_editing(&self) -> bool {
+        self.custom.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut popup = MethodPopup::new(Arc::default());
+
+        popup.open("post");
+        popup.event(key(KeyCode::Down));
+        assert_eq!(
+            popup.event(key(KeyCode::Enter)),
+            Action::SetMethod(String::from("PUT"))
+        );
+        assert_eq!(popup.event(key(KeyCode::Esc)), Action::CloseMethodPicker);
+
+        // Esc in the custom method box only closes the box
+        popup.open("purge");
+        assert!(popup.is_editing());
+        assert_eq!(popup.event(key(KeyCode::Esc)), Action::None);
+        assert!(!popup.is_editing());
+
+        popup.open("purge");
+        popup.event(key(KeyCode::Char('x')));
+        assert_eq!(
+            popup.event(key(KeyCode::Enter)),
+            Action::SetMethod(String::from("PURGEX"))
+        );
+    }
+}
@@ -1,9 +1,28 @@
 pub mod command_bar;
+pub mod component;
 pub mod folder_popup;
+pub mod format;
+pub mod help;
 pub mod list_component;
 pub mod main_pane;
+pub mod markup;
+pub mod method_popup;
+pub mod palette;
+pub mod response_buffer;
+pub mod response_viewer;
+pub mod search_bar;
+pub mod spinner;
+pub mod toast;
+pub mod vim;
 
 pub use command_bar::{CommandComponent, CommandType};
+pub use component::{Action, Component};
 pub use folder_popup::FolderPopup;
+pub use help::HelpOverlay;
 pub use list_component::ListComponent;
 pub use main_pane::MainPaneComponent;
+pub use method_popup::MethodPopup;
+pub use palette::{CommandPalette, PaletteEntry};
+pub use response_viewer::ResponseViewer;
+pub use toast::{Toast, Toasts};
+pub use vim::Vim;
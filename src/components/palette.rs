@@ -0,0 +1,319 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::components::{folder_popup::centered_rect, Action, Component};
+use crate::foldertree::EndpointEntry;
+use crate::fuzzy::fuzzy_match;
+use crate::theme::Theme;
+
+// Something the palette can run, shown as its text with a hint next to it
+pub struct PaletteEntry {
+    text: String,
+    hint: String,
+    target: Target,
+}
+
+enum Target {
+    Command(Action),
+
+    // Path of an endpoint to jump to
+    Endpoint(String),
+}
+
+impl PaletteEntry {
+    pub fn command(label: &str, keys: String, action: Action) -> Self {
+        Self {
+            text: String::from(label),
+            hint: keys,
+            target: Target::Command(action),
+        }
+    }
+
+    // Found by its method, name and URL
+    pub fn endpoint(endpoint: &EndpointEntry) -> Self {
+        Self {
+            text: format!("{} {}  {}", endpoint.method, endpoint.name, endpoint.url),
+            hint: endpoint.folders.join(" / "),
+            target: Target::Endpoint(endpoint.path.clone()),
+        }
+    }
+
+    fn action(&self) -> Action {
+        match &self.target {
+            Target::Command(action) => action.clone(),
+            Target::Endpoint(path) => Action::SelectEndpoint(path.clone()),
+        }
+    }
+}
+
+// Ctrl-P popup that finds commands and endpoints by typing part of them
+pub struct CommandPalette<'a> {
+    // Query being typed, only there while the palette is open
+    input: Option<TextArea<'a>>,
+
+    entries: Vec<PaletteEntry>,
+
+    // Indices of the matching entries, best first, with the positions of the
+    // matched characters
+    results: Vec<(usize, Vec<usize>)>,
+    state: ListState,
+
+    theme: Arc<Theme>,
+}
+
+impl CommandPalette<'_> {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            input: None,
+            entries: Vec::new(),
+            results: Vec::new(),
+            state: ListState::default(),
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, entries: Vec<PaletteEntry>) {
+        let mut input = TextArea::default();
+        input.set_cursor_line_style(Style::default());
+
+        self.input = Some(input);
+        self.entries = entries;
+        self.update();
+    }
+
+    pub fn close(&mut self) {
+        self.input = None;
+        self.entries.clear();
+        self.results.clear();
+    }
+
+    fn query(&self) -> &str {
+        self.input
+            .as_ref()
+            .and_then(|input| input.lines().first())
+            .map_or("", String::as_str)
+    }
+
+    fn update(&mut self) {
+        let query = self.query().to_string();
+
+        let mut results = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| fuzzy_match(&query, &entry.text).map(|m| (i, m)))
+            .collect::<Vec<_>>();
+
+        // Stable, so equally good ones stay in their order with commands first
+        results.sort_by_key(|(_, m)| -m.score);
+
+        self.results = results.into_iter().map(|(i, m)| (i, m.positions)).collect();
+        self.state.select((!self.results.is_empty()).then_some(0));
+    }
+
+    fn select(&mut self, forward: bool) {
+        let len = self.results.len();
+        if len == 0 {
+            return;
+        }
+
+        let i = self.state.selected().unwrap_or(0);
+        let i = match forward {
+            true => (i + 1) % len,
+            false => (i + len - 1) % len,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+impl Component for CommandPalette<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        let ctrl = ev.modifiers.contains(KeyModifiers::CONTROL);
+
+        match ev.code {
+            KeyCode::Esc => self.close(),
+            KeyCode::Enter => {
+                let selected = self
+                    .state
+                    .selected()
+                    .and_then(|i| self.results.get(i))
+                    .map(|(entry, _)| self.entries[*entry].action());
+
+                self.close();
+
+                return selected.unwrap_or(Action::None);
+            }
+            KeyCode::Down | KeyCode::Tab => self.select(true),
+            KeyCode::Up | KeyCode::BackTab => self.select(false),
+            KeyCode::Char('n') if ctrl => self.select(true),
+            KeyCode::Char('p') if ctrl => self.select(false),
+            _ => {
+                let changed = match &mut self.input {
+                    Some(input) => input.input(ev),
+                    None => false,
+                };
+
+                if changed {
+                    self.update();
+                }
+            }
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        if self.input.is_none() {
+            return;
+        }
+
+        let area = centered_rect(60, 60, r);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Command palette");
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(block.inner(area));
+
+        let items = self
+            .results
+            .iter()
+            .map(|(i, positions)| {
+                let entry = &self.entries[*i];
+                let mut spans = highlighted(&entry.text, positions, Style::default(), &self.theme);
+
+                if !entry.hint.is_empty() {
+                    spans.push(Span::styled(
+                        format!("  {}", entry.hint),
+                        self.theme.fg(self.theme.muted),
+                    ));
+                }
+
+                ListItem::new(Spans::from(spans))
+            })
+            .collect::<Vec<ListItem>>();
+
+        let list = List::new(items).highlight_style(self.theme.selected());
+
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        if let Some(input) = &mut self.input {
+            input.set_block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick),
+            );
+            f.render_widget(input.widget(), chunks[0]);
+        }
+
+        f.render_stateful_widget(list, chunks[1], &mut self.state);
+    }
+
+    fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+}
+
+// The text split into runs of matched and unmatched characters
+pub fn highlighted(
+    text: &str,
+    positions: &[usize],
+    style: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let matched = style.patch(theme.highlighted());
+
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let is_match = positions.contains(&i);
+
+        if is_match != run_matched && !run.is_empty() {
+            let run_style = if run_matched { matched } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+
+        run_matched = is_match;
+        run.push(c);
+    }
+
+    if !run.is_empty() {
+        let run_style = if run_matched { matched } else { style };
+        spans.push(Span::styled(run, run_style));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    fn type_in(palette: &mut CommandPalette, text: &str) {
+        for c in text.chars() {
+            palette.event(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_palette() {
+        let endpoint = EndpointEntry {
+            path: String::from("/root/0/items/1"),
+            name: String::from("Change users"),
+            method: String::from("PUT"),
+            url: String::from("http://localhost:3003/change_user"),
+            folders: vec![String::from("First")],
+        };
+
+        let mut palette = CommandPalette::new(Arc::default());
+        palette.open(vec![
+            PaletteEntry::command("Send request", String::from("s"), Action::SendRequest),
+            PaletteEntry::command("Quit", String::from("q"), Action::Quit),
+            PaletteEntry::endpoint(&endpoint),
+        ]);
+        assert_eq!(palette.results.len(), 3);
+
+        type_in(&mut palette, "3003");
+        assert_eq!(palette.results.len(), 1);
+        assert_eq!(
+            palette.event(KeyEvent::from(KeyCode::Enter)),
+            Action::SelectEndpoint(endpoint.path.clone())
+        );
+        assert!(!palette.is_editing());
+
+        palette.open(vec![
+            PaletteEntry::command("Send request", String::from("s"), Action::SendRequest),
+            PaletteEntry::command("Quit", String::from("q"), Action::Quit),
+        ]);
+        type_in(&mut palette, "qt");
+        assert_eq!(palette.event(KeyEvent::from(KeyCode::Enter)), Action::Quit);
+    }
+
+    #[test]
+    fn test_highlighted() {
+        let spans = highlighted("Send", &[0, 1], Style::default(), &Theme::default());
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].content, "Se");
+        assert_eq!(spans[1].content, "nd");
+
+        // Unmatched characters keep the style they came with
+        let style = Style::default().fg(Color::Green);
+        let spans = highlighted("POST", &[0], style, &Theme::default());
+        assert_eq!(spans[1].style, style);
+    }
+}
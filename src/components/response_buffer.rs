@@ -0,0 +1,129 @@
+use bytes::Bytes;
+use std::{borrow::Cow, ops::Range};
+
+use crate::http::content;
+
+// Text of a response kept in one piece together with where each of its lines
+// is, so any window of it can be drawn without walking through the rest. The
+// text is always valid UTF-8.
+pub struct ResponseBuffer {
+    text: Bytes,
+    lines: Vec<Range<usize>>,
+}
+
+impl ResponseBuffer {
+    pub fn new(text: String) -> Self {
+        Self::index(Bytes::from(text))
+    }
+
+    // A body in the charset from its content type. Bodies that are UTF-8
+    // already share their bytes with the response instead of being copied
+    pub fn decode(content_type: Option<&str>, body: &Bytes) -> Self {
+        let text = match content::decode_text(content_type, body) {
+            Cow::Borrowed(text) => body.slice_ref(text.as_bytes()),
+            Cow::Owned(text) => Bytes::from(text),
+        };
+
+        Self::index(text)
+    }
+
+    fn index(text: Bytes) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        for (i, byte) in text.iter().enumerate() {
+            if *byte == b'\n' {
+                let end = if i > start && text[i - 1] == b'\r' {
+                    i - 1
+                } else {
+                    i
+                };
+
+                lines.push(start..end);
+                start = i + 1;
+            }
+        }
+
+        if start < text.len() || lines.is_empty() {
+            lines.push(start..text.len());
+        }
+
+        Self { text, lines }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    // Size of the whole text in bytes
+    pub fn size(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.text).unwrap_or_default()
+    }
+
+    pub fn line(&self, i: usize) -> &str {
+        self.lines
+            .get(i)
+            .and_then(|range| std::str::from_utf8(&self.text[range.clone()]).ok())
+            .unwrap_or_default()
+    }
+
+    // Up to `width` characters of a line starting at character `column`,
+    // together with the byte offset they start at. A minified body can be a
+    // single line of several megabytes
+    pub fn window(&self, i: usize, column: usize, width: usize) -> (usize, &str) {
+        let line = self.line(i);
+
+        let start = line
+            .char_indices()
+            .nth(column)
+            .map_or(line.len(), |(start, _)| start);
+        let rest = &line[start..];
+
+        let end = rest
+            .char_indices()
+            .nth(width)
+            .map_or(rest.len(), |(end, _)| end);
+
+        (start, &rest[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let buffer = ResponseBuffer::new(String::from("first\r\nsecond\n\nżółw jest"));
+
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.line(0), "first");
+        assert_eq!(buffer.line(2), "");
+        assert_eq!(buffer.line(9), "");
+        assert_eq!(buffer.window(3, 0, 3), (0, "żół"));
+        assert_eq!(buffer.window(3, 2, 4), (4, "łw j"));
+        assert_eq!(buffer.window(1, 0, 100), (0, "second"));
+        assert_eq!(buffer.window(1, 10, 5), (6, ""));
+
+        assert_eq!(ResponseBuffer::new(String::from("a\n")).len(), 1);
+        assert_eq!(ResponseBuffer::new(String::new()).len(), 1);
+    }
+
+    #[test]
+    fn test_decode() {
+        let body = Bytes::from_static(b"{\"a\": 1}\n");
+        let buffer = ResponseBuffer::decode(Some("application/json"), &body);
+
+        // Same bytes as the body, not a copy of them
+        assert_eq!(buffer.text.as_ptr(), body.as_ptr());
+        assert_eq!(buffer.as_str(), "{\"a\": 1}\n");
+
+        let body = Bytes::from_static(b"caf\xe9");
+        let buffer = ResponseBuffer::decode(Some("text/plain; charset=latin1"), &body);
+        assert_eq!(buffer.line(0), "café");
+    }
+}
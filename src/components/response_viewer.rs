@@ -0,0 +1,1478 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use regex::Regex;
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{self, Write},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::components::{
+    format::format_size, markup::markup_lines, response_buffer::ResponseBuffer,
+};
+use crate::error::Error;
+use crate::filter;
+use crate::http::{content, Response};
+use crate::keymap::{Keymap, Scope, Shortcut};
+use crate::theme::Theme;
+
+// Recent filters remembered for each endpoint
+const FILTER_HISTORY: usize = 20;
+
+// JSON larger than this is shown as plain text, without highlighting or folding
+const HIGHLIGHT_LIMIT: usize = 1024 * 1024;
+
+// Bytes on each line of the hex dump
+const HEX_WIDTH: usize = 16;
+
+// Where the printable characters start on a hex dump line, after the offset,
+// the bytes and the `|`
+const HEX_TEXT: usize = 10 + HEX_WIDTH * 3 + 1 + 2;
+
+// How a body that isn't JSON is shown, from its content type
+#[derive(Clone, Copy, PartialEq)]
+enum TextFormat {
+    Json,
+    Xml,
+    Html,
+    Form,
+    Plain,
+}
+
+impl TextFormat {
+    fn from_media_type(media_type: &str) -> Self {
+        match media_type {
+            "text/html" | "application/xhtml+xml" => TextFormat::Html,
+            "application/xml" | "text/xml" => TextFormat::Xml,
+            t if t.ends_with("+xml") => TextFormat::Xml,
+            "application/x-www-form-urlencoded" => TextFormat::Form,
+            "application/json" => TextFormat::Json,
+            t if t.ends_with("+json") => TextFormat::Json,
+            _ => TextFormat::Plain,
+        }
+    }
+}
+
+// What's on screen, each made from the body when it's needed
+enum View {
+    // Highlighted lines in `lines`
+    Lines,
+
+    // The body text as it is, for plain text and bodies too large to highlight
+    Text,
+
+    // Filter results too large to highlight, printed one after another
+    Results(ResponseBuffer),
+}
+
+// A single rendered line together with the JSONPath of the value it belongs to
+struct ViewLine {
+    spans: Vec<Span<'static>>,
+    path: String,
+    foldable: bool,
+}
+
+pub struct ResponseViewer<'a> {
+    response: Option<Arc<Response>>,
+
+    // Body decoded with its charset, sharing the response's bytes when it's
+    // UTF-8 already. Binary bodies are only decoded once they're shown as text
+    text: ResponseBuffer,
+
+    // The body parsed as JSON, only once something needs it: right away for
+    // bodies small enough to highlight, on the first filter for larger ones
+    json: Option<Value>,
+    parsed: bool,
+
+    format: TextFormat,
+
+    // Path of the endpoint the response belongs to
+    endpoint: String,
+
+    // Applied jq filter and what it produced, None when nothing is filtered
+    filter: String,
+    results: Option<Vec<Value>>,
+
+    // Filter being typed, only there while the filter bar is open
+    filter_box: Option<TextArea<'a>>,
+    filter_error: Option<String>,
+
+    // Recent filters by endpoint path, newest first, and the one recalled
+    // with Up/Down in the filter bar
+    filter_history: HashMap<String, Vec<String>>,
+    history_pos: Option<usize>,
+
+    // JSONPaths of the folded objects and arrays
+    folded: HashSet<String>,
+
+    view: View,
+    lines: Vec<ViewLine>,
+
+    // Size of what's shown when it's too large to highlight
+    too_large: Option<usize>,
+
+    // Hex dump instead of text, on from the start for binary bodies
+    hex: bool,
+
+    // Path prompt for saving the body and how the last save went
+    save_box: Option<TextArea<'a>>,
+    saved: Option<Result<String, String>>,
+
+    cursor: usize,
+    scroll: usize,
+
+    // First character shown of every line, lines wider than the pane are cut
+    column: usize,
+
+    // Searched pattern, where it matches (line and byte range, in order) and
+    // the match that was jumped to last
+    search: Option<Regex>,
+    matches: Vec<(usize, Range<usize>)>,
+    current_match: Option<usize>,
+
+    // Size of the last draw, used for paging and scrolling to matches
+    height: usize,
+    width: usize,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+}
+
+impl<'a> ResponseViewer<'a> {
+    pub fn new(keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        Self {
+            response: None,
+            text: ResponseBuffer::new(String::new()),
+            json: None,
+            parsed: false,
+            format: TextFormat::Plain,
+            endpoint: String::new(),
+            filter: String::new(),
+            results: None,
+            filter_box: None,
+            filter_error: None,
+            filter_history: HashMap::new(),
+            history_pos: None,
+            folded: HashSet::new(),
+            view: View::Lines,
+            lines: Vec::new(),
+            too_large: None,
+            hex: false,
+            save_box: None,
+            saved: None,
+            cursor: 0,
+            scroll: 0,
+            column: 0,
+            search: None,
+            matches: Vec::new(),
+            current_match: None,
+            height: 0,
+            width: 0,
+            keymap,
+            theme,
+        }
+    }
+
+    pub fn set_response(&mut self, endpoint: &str, response: Option<Arc<Response>>) {
+        let same = match (&self.response, &response) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+
+        if same && self.endpoint == endpoint {
+            return;
+        }
+
+        // A filter is kept for new responses of the same endpoint only
+        if self.endpoint != endpoint {
+            self.endpoint = String::from(endpoint);
+            self.filter.clear();
+            self.filter_box = None;
+        }
+
+        self.hex = response.as_ref().is_some_and(|r| r.is_binary());
+        self.format = response
+            .as_ref()
+            .and_then(|r| r.content_type())
+            .map_or(TextFormat::Plain, |t| {
+                TextFormat::from_media_type(&content::media_type(t))
+            });
+        self.response = response;
+        self.text = ResponseBuffer::new(String::new());
+        self.json = None;
+        self.parsed = false;
+        self.saved = None;
+        self.column = 0;
+
+        if !self.hex {
+            self.decode();
+        }
+
+        let filter = self.filter.clone();
+        self.run_filter(&filter);
+    }
+
+    fn decode(&mut self) {
+        if let Some(response) = &self.response {
+            self.text = ResponseBuffer::decode(response.content_type(), &response.body);
+        }
+
+        // Bodies without a JSON content type are still tried when they look like it
+        let json = self.format == TextFormat::Json
+            || self.format == TextFormat::Plain
+                && self.text.as_str().trim_start().starts_with(['{', '[']);
+
+        if json {
+            self.format = TextFormat::Json;
+
+            if self.text.size() <= HIGHLIGHT_LIMIT {
+                self.parse();
+            }
+        }
+    }
+
+    fn parse(&mut self) {
+        if !self.parsed {
+            self.json = serde_json::from_str(self.text.as_str()).ok();
+            self.parsed = true;
+        }
+    }
+
+    // Typing a filter or where to save the body
+    pub fn is_editing(&self) -> bool {
+        self.filter_box.is_some() || self.save_box.is_some()
+    }
+
+    pub fn set_search(&mut self, search: Option<Regex>) {
+        self.search = search;
+        self.find_matches();
+    }
+
+    // Moves the cursor to the next match after (or on, with `here`) the
+    // cursor line, wrapping around at either end
+    pub fn next_match(&mut self, forward: bool, here: bool) -> bool {
+        if self.matches.is_empty() {
+            return false;
+        }
+
+        let cursor = self.cursor;
+        let index = if forward {
+            self.matches
+                .iter()
+                .position(|(line, _)| *line > cursor || (here && *line == cursor))
+                .unwrap_or(0)
+        } else {
+            self.matches
+                .iter()
+                .rposition(|(line, _)| *line < cursor || (here && *line == cursor))
+                .unwrap_or(self.matches.len() - 1)
+        };
+
+        self.current_match = Some(index);
+        self.cursor = self.matches[index].0;
+
+        // Scrolled sideways to the match when it's off screen
+        let start = self.matches[index].1.start;
+        let column = self.line_text(self.cursor)[..start].chars().count();
+        if column < self.column || column >= self.column + self.width {
+            self.column = column.saturating_sub(self.width / 4);
+        }
+
+        true
+    }
+
+    // Like "3/12", for the search bar
+    pub fn match_status(&self) -> String {
+        match (self.matches.len(), self.current_match) {
+            (0, _) => String::from("no matches"),
+            (len, Some(current)) => format!("{}/{}", current + 1, len),
+            (len, None) => format!("{} matches", len),
+        }
+    }
+
+    pub fn current_path(&self) -> Option<&str> {
+        self.lines
+            .get(self.cursor)
+            .map(|line| line.path.as_str())
+            .filter(|path| !path.is_empty())
+    }
+
+    pub fn event(&mut self, ev: KeyEvent) {
+        if self.filter_box.is_some() {
+            self.filter_event(ev);
+            return;
+        }
+
+        if self.save_box.is_some() {
+            self.save_event(ev);
+            return;
+        }
+
+        // The outcome of a save stays until the next key
+        self.saved = None;
+
+        let last = self.line_count().saturating_sub(1);
+        let page = self.height.max(1);
+
+        match self.keymap.lookup(ev, Scope::Response) {
+            Some(Shortcut::Down) => self.cursor = (self.cursor + 1).min(last),
+            Some(Shortcut::Up) => self.cursor = self.cursor.saturating_sub(1),
+            Some(Shortcut::PageDown) => self.cursor = (self.cursor + page).min(last),
+            Some(Shortcut::PageUp) => self.cursor = self.cursor.saturating_sub(page),
+            Some(Shortcut::Top) => self.cursor = 0,
+            Some(Shortcut::Bottom) => self.cursor = last,
+            Some(Shortcut::Fold) => self.toggle_fold(),
+            Some(Shortcut::Filter) => self.open_filter(),
+            Some(Shortcut::ScrollLeft) => {
+                self.column = self.column.saturating_sub(self.scroll_step());
+            }
+            Some(Shortcut::ScrollRight) => {
+                let widest = self.widest_line();
+                self.column = (self.column + self.scroll_step()).min(widest.saturating_sub(1));
+            }
+            Some(Shortcut::Hex) if self.response.is_some() => {
+                self.hex = !self.hex;
+                self.cursor = 0;
+                self.scroll = 0;
+                self.column = 0;
+
+                if !self.hex && !self.parsed && self.text.size() == 0 {
+                    self.decode();
+                }
+                self.rebuild();
+            }
+            Some(Shortcut::SaveBody) => self.open_save(),
+            _ => {}
+        }
+    }
+
+    fn open_save(&mut self) {
+        let response = match &self.response {
+            Some(response) => response,
+            None => return,
+        };
+
+        let name = format!("response.{}", content::extension(response.content_type()));
+        let mut save_box = TextArea::from([name]);
+        save_box.move_cursor(CursorMove::End);
+
+        self.save_box = Some(save_box);
+    }
+
+    fn save_event(&mut self, ev: KeyEvent) {
+        let save_box = match &mut self.save_box {
+            Some(save_box) => save_box,
+            None => return,
+        };
+
+        match ev.code {
+            KeyCode::Esc => self.save_box = None,
+            KeyCode::Enter => {
+                let path = save_box.lines()[0].trim().to_string();
+
+                if path.is_empty() {
+                    return;
+                }
+
+                if let Some(response) = &self.response {
+                    self.saved = Some(save_body(Path::new(&path), &response.body));
+                }
+
+                self.save_box = None;
+            }
+            _ => {
+                save_box.input(ev);
+            }
+        }
+    }
+
+    fn open_filter(&mut self) {
+        let mut filter_box = TextArea::from([self.filter.clone()]);
+        filter_box.move_cursor(CursorMove::End);
+
+        self.filter_box = Some(filter_box);
+        self.filter_error = None;
+        self.history_pos = None;
+    }
+
+    // The result follows every keystroke, Enter keeps the filter and Esc goes
+    // back to the one that was applied before
+    fn filter_event(&mut self, ev: KeyEvent) {
+        let filter_box = match &mut self.filter_box {
+            Some(filter_box) => filter_box,
+            None => return,
+        };
+
+        match ev.code {
+            KeyCode::Esc => {
+                self.filter_box = None;
+                let filter = self.filter.clone();
+                self.run_filter(&filter);
+            }
+            KeyCode::Enter => {
+                let typed = filter_box.lines()[0].trim().to_string();
+                self.filter_box = None;
+
+                if self.run_filter(&typed) {
+                    self.filter = typed.clone();
+                    self.remember_filter(typed);
+                } else {
+                    let filter = self.filter.clone();
+                    self.run_filter(&filter);
+                }
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let history = self.filter_history.get(&self.endpoint);
+                let len = history.map_or(0, Vec::len);
+
+                let pos = match (ev.code, self.history_pos) {
+                    (KeyCode::Up, None) if len > 0 => Some(0),
+                    (KeyCode::Up, Some(pos)) => Some((pos + 1).min(len.saturating_sub(1))),
+                    (KeyCode::Down, Some(0)) => None,
+                    (KeyCode::Down, Some(pos)) => Some(pos - 1),
+                    (_, pos) => pos,
+                };
+
+                let recalled = pos
+                    .and_then(|pos| history.and_then(|h| h.get(pos)))
+                    .cloned()
+                    .unwrap_or_default();
+
+                let mut recalled_box = TextArea::from([recalled.clone()]);
+                recalled_box.move_cursor(CursorMove::End);
+
+                self.history_pos = pos;
+                self.filter_box = Some(recalled_box);
+                self.run_filter(&recalled);
+            }
+            _ => {
+                if filter_box.input(ev) {
+                    let typed = filter_box.lines()[0].clone();
+                    self.run_filter(&typed);
+                }
+            }
+        }
+    }
+
+    fn remember_filter(&mut self, filter: String) {
+        if filter.is_empty() {
+            return;
+        }
+
+        let history = self
+            .filter_history
+            .entry(self.endpoint.clone())
+            .or_default();
+
+        history.retain(|f| *f != filter);
+        history.insert(0, filter);
+        history.truncate(FILTER_HISTORY);
+    }
+
+    // Shows what the filter produces, a filter that fails leaves the view as
+    // it was and only reports the error
+    fn run_filter(&mut self, filter: &str) -> bool {
+        if !filter.trim().is_empty() {
+            self.parse();
+        }
+
+        let results = match (&self.json, filter.trim().is_empty()) {
+            (_, true) => Ok(None),
+            (Some(json), false) => filter::apply(filter, json).map(Some),
+            (None, false) => Err(Error::Script(String::from("Response isn't JSON"))),
+        };
+
+        match results {
+            Ok(results) => {
+                self.results = results;
+                self.filter_error = None;
+                self.folded.clear();
+                self.cursor = 0;
+                self.scroll = 0;
+                self.rebuild();
+                true
+            }
+            Err(e) => {
+                self.filter_error = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        let footer = if self.is_editing() { 2 } else { 1 };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(footer)])
+            .split(r);
+
+        self.height = chunks[0].height as usize;
+
+        // Keep the cursor on screen
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + self.height {
+            self.scroll = self.cursor + 1 - self.height;
+        }
+
+        self.width = chunks[0].width as usize;
+        let visible = self.scroll..(self.scroll + self.height).min(self.line_count());
+
+        // Only the lines on screen are ever turned into spans
+        let text = visible
+            .map(|i| {
+                let start = self.matches.partition_point(|(l, _)| *l < i);
+                let ranges = self.matches[start..]
+                    .iter()
+                    .enumerate()
+                    .take_while(|(_, (l, _))| *l == i)
+                    .map(|(n, (_, range))| (range.clone(), self.current_match == Some(start + n)));
+
+                // Text lines are cut to the window first, so the rest of a
+                // long line is never copied. Highlighted lines are short
+                // enough to be cut once they're marked
+                let spans = match (self.buffer(), &self.response) {
+                    (Some(buffer), _) => {
+                        let (offset, window) = buffer.window(i, self.column, self.width);
+                        let ranges = ranges
+                            .map(|(range, current)| {
+                                let start = range.start.saturating_sub(offset);
+                                let end = range.end.saturating_sub(offset);
+                                (start..end, current)
+                            })
+                            .collect::<Vec<(Range<usize>, bool)>>();
+
+                        mark_matches(vec![Span::raw(String::from(window))], &ranges, &self.theme)
+                    }
+                    (None, response) => {
+                        let spans = match response {
+                            Some(response) if self.hex => hex_line(&response.body, i, &self.theme),
+                            _ => self.lines[i].spans.clone(),
+                        };
+                        let ranges = ranges.collect::<Vec<(Range<usize>, bool)>>();
+
+                        skip_columns(mark_matches(spans, &ranges, &self.theme), self.column)
+                    }
+                };
+
+                let spans = Spans::from(spans);
+
+                if i == self.cursor {
+                    highlight(spans, &self.theme)
+                } else {
+                    spans
+                }
+            })
+            .collect::<Vec<Spans>>();
+
+        f.render_widget(Paragraph::new(text), chunks[0]);
+
+        let mut status = Vec::new();
+
+        match &self.saved {
+            Some(Ok(message)) => status.push(Span::styled(
+                format!("{} · ", message),
+                self.theme.fg(self.theme.success),
+            )),
+            Some(Err(e)) => status.push(Span::styled(
+                format!("{} · ", e),
+                self.theme.fg(self.theme.error),
+            )),
+            None => {}
+        }
+
+        if let (true, Some(response)) = (self.hex, &self.response) {
+            status.push(Span::styled(
+                format!("Hex dump of {} · ", format_size(response.body.len())),
+                self.theme.fg(self.theme.muted),
+            ));
+        }
+
+        if let Some(size) = self.too_large {
+            status.push(Span::styled(
+                format!(
+                    "Large response ({}), highlighting and folding are off · ",
+                    format_size(size)
+                ),
+                self.theme.fg(self.theme.warning),
+            ));
+        }
+
+        if let Some(e) = &self.filter_error {
+            status.push(Span::styled(e.clone(), self.theme.fg(self.theme.error)));
+        } else {
+            if let Some(results) = &self.results {
+                let unit = if results.len() == 1 {
+                    "result"
+                } else {
+                    "results"
+                };
+                status.push(Span::styled(
+                    format!("{} {} · ", results.len(), unit),
+                    self.theme.fg(self.theme.highlight),
+                ));
+            }
+            status.push(Span::styled(
+                String::from(self.current_path().unwrap_or_default()),
+                self.theme.fg(self.theme.muted),
+            ));
+        }
+
+        if self.filter_box.is_none() && !self.filter.is_empty() {
+            status.insert(
+                0,
+                Span::styled(
+                    format!("jq: {}  ", self.filter),
+                    self.theme.fg(self.theme.label),
+                ),
+            );
+        }
+
+        let footer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(chunks[1]);
+
+        f.render_widget(Paragraph::new(Spans::from(status)), footer[0]);
+
+        let prompt = match (&mut self.filter_box, &mut self.save_box) {
+            (Some(filter_box), _) => Some(("jq> ", filter_box)),
+            (None, Some(save_box)) => Some(("Save to: ", save_box)),
+            (None, None) => None,
+        };
+
+        if let Some((label, input_box)) = prompt {
+            let input = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(label.len() as u16), Constraint::Min(0)])
+                .split(footer[1]);
+
+            input_box.set_cursor_line_style(Style::default());
+
+            f.render_widget(
+                Paragraph::new(Span::styled(label, self.theme.fg(self.theme.label))),
+                input[0],
+            );
+            f.render_widget(input_box.widget(), input[1]);
+        }
+    }
+
+    // Folds or unfolds the JSON under the cursor, false when it isn't an
+    // object or array or already is that way
+    pub fn set_folded(&mut self, folded: bool) -> bool {
+        match self.lines.get(self.cursor) {
+            Some(line) if line.foldable && self.folded.contains(&line.path) != folded => {
+                self.toggle_fold();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn toggle_fold(&mut self) {
+        let line = match self.lines.get(self.cursor) {
+            Some(line) if line.foldable => line,
+            _ => return,
+        };
+
+        let path = line.path.clone();
+
+        if !self.folded.remove(&path) {
+            self.folded.insert(path.clone());
+        }
+
+        self.rebuild();
+
+        // Stay on the line that was just (un)folded
+        self.cursor = self
+            .lines
+            .iter()
+            .position(|line| line.path == path)
+            .unwrap_or(0);
+    }
+
+    fn rebuild(&mut self) {
+        self.lines.clear();
+        self.view = View::Lines;
+        self.too_large = None;
+
+        let values = match (self.hex, &self.results, &self.json) {
+            (true, _, _) => None,
+            (false, Some(results), _) => Some(results.iter().collect::<Vec<&Value>>()),
+            (false, None, Some(json)) => Some(vec![json]),
+            (false, None, None) => None,
+        };
+
+        match values {
+            Some(values) => {
+                let size = match &self.results {
+                    None => self.text.size(),
+                    Some(_) => values
+                        .iter()
+                        .map(|v| serde_json::to_vec(v).map_or(0, |v| v.len()))
+                        .sum(),
+                };
+
+                if size > HIGHLIGHT_LIMIT {
+                    // The body itself is shown as it came, only filter
+                    // results have to be printed
+                    self.view = match &self.results {
+                        None => View::Text,
+                        Some(_) => View::Results(ResponseBuffer::new(
+                            values
+                                .iter()
+                                .map(|v| serde_json::to_string_pretty(v).unwrap_or_default())
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        )),
+                    };
+                    self.too_large = Some(size);
+                } else {
+                    let mut json_lines = JsonLines {
+                        folded: &self.folded,
+                        theme: &self.theme,
+                        lines: &mut self.lines,
+                    };
+
+                    if values.len() == 1 {
+                        json_lines.push(values[0], None, "$", 0, false);
+                    } else {
+                        // Several results are shown one after another, the way jq prints them
+                        for (i, value) in values.iter().enumerate() {
+                            let path = format!("$[{}]", i);
+                            json_lines.push(value, None, &path, 0, false);
+                        }
+                    }
+                }
+            }
+            // Hex lines are made when they are drawn
+            None if self.hex => {}
+            None if self.response.is_none() => {}
+            None => {
+                let text = self.text.as_str();
+
+                let spans = match self.format {
+                    _ if text.len() > HIGHLIGHT_LIMIT => None,
+                    TextFormat::Xml => Some(markup_lines(text, false, &self.theme)),
+                    TextFormat::Html => Some(markup_lines(text, true, &self.theme)),
+                    TextFormat::Form => Some(form_lines(text, &self.theme)),
+                    TextFormat::Json | TextFormat::Plain => None,
+                };
+
+                match spans {
+                    Some(spans) => self.lines.extend(spans.into_iter().map(|spans| ViewLine {
+                        spans,
+                        path: String::new(),
+                        foldable: false,
+                    })),
+                    None => {
+                        if self.format != TextFormat::Plain && text.len() > HIGHLIGHT_LIMIT {
+                            self.too_large = Some(text.len());
+                        }
+                        self.view = View::Text;
+                    }
+                }
+            }
+        }
+
+        self.cursor = self.cursor.min(self.line_count().saturating_sub(1));
+        self.find_matches();
+    }
+
+    // Text shown line by line as it is, None when the lines are highlighted
+    fn buffer(&self) -> Option<&ResponseBuffer> {
+        match &self.view {
+            View::Lines => None,
+            View::Text => Some(&self.text),
+            View::Results(buffer) => Some(buffer),
+        }
+    }
+
+    fn line_count(&self) -> usize {
+        match (self.buffer(), &self.response) {
+            (Some(buffer), _) => buffer.len(),
+            (None, Some(response)) if self.hex => response.body.len().div_ceil(HEX_WIDTH),
+            _ => self.lines.len(),
+        }
+    }
+
+    fn line_text(&self, i: usize) -> Cow<'_, str> {
+        let spans = match (self.buffer(), &self.response) {
+            (Some(buffer), _) => return Cow::Borrowed(buffer.line(i)),
+            (None, Some(response)) if self.hex => hex_line(&response.body, i, &self.theme),
+            _ => self
+                .lines
+                .get(i)
+                .map_or_else(Vec::new, |line| line.spans.clone()),
+        };
+
+        Cow::Owned(spans.iter().map(|s| s.content.as_ref()).collect())
+    }
+
+    // Length of the longest line on screen, how far it can be scrolled sideways
+    fn widest_line(&self) -> usize {
+        let visible = self.scroll..(self.scroll + self.height).min(self.line_count());
+
+        visible
+            .map(|i| match self.buffer() {
+                Some(buffer) => buffer.line(i).chars().count(),
+                None => self.line_text(i).chars().count(),
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Half a pane, so what was at the edge stays in view
+    fn scroll_step(&self) -> usize {
+        (self.width / 2).max(1)
+    }
+
+    fn find_matches(&mut self) {
+        self.current_match = None;
+
+        let search = match &self.search {
+            Some(search) => search,
+            None => {
+                self.matches.clear();
+                return;
+            }
+        };
+
+        // Binary bodies are searched as they are rather than line by line
+        // through the dump, matches are marked on its printable characters
+        if let (true, Some(response)) = (self.hex, &self.response) {
+            self.matches = hex_matches(&response.body, search.as_str());
+            return;
+        }
+
+        let mut matches = Vec::new();
+
+        for i in 0..self.line_count() {
+            let text = self.line_text(i);
+
+            for m in search.find_iter(&text).filter(|m| !m.range().is_empty()) {
+                matches.push((i, m.range()));
+            }
+        }
+
+        self.matches = matches;
+    }
+}
+
+// Relative paths are taken from the working directory, next to the collection.
+// An existing file is never replaced, another name has to be picked instead
+pub fn save_body(path: &Path, body: &[u8]) -> Result<String, String> {
+    let path = std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(body));
+
+    match written {
+        Ok(_) => Ok(format!(
+            "Saved {} to {}",
+            format_size(body.len()),
+            path.display()
+        )),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            Err(format!("{} already exists", path.display()))
+        }
+        Err(e) => Err(format!("Couldn't save to {}: {}", path.display(), e)),
+    }
+}
+
+// Restyles the byte ranges of a line that matched the search, `true` marks
+// the current match
+fn mark_matches(
+    spans: Vec<Span<'static>>,
+    ranges: &[(Range<usize>, bool)],
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    if ranges.is_empty() {
+        return spans;
+    }
+
+    let mut marked = Vec::new();
+    let mut offset = 0;
+
+    for span in spans {
+        let text = span.content.as_ref();
+        let end = offset + text.len();
+        let mut pos = offset;
+
+        for (range, current) in ranges {
+            let (start, stop) = (range.start.max(pos), range.end.min(end));
+
+            if start >= stop {
+                continue;
+            }
+
+            if start > pos {
+                marked.push(Span::styled(
+                    String::from(&text[pos - offset..start - offset]),
+                    span.style,
+                ));
+            }
+
+            marked.push(Span::styled(
+                String::from(&text[start - offset..stop - offset]),
+                theme.search_match(*current),
+            ));
+            pos = stop;
+        }
+
+        if pos < end {
+            marked.push(Span::styled(
+                String::from(&text[pos - offset..]),
+                span.style,
+            ));
+        }
+
+        offset = end;
+    }
+
+    marked
+}
+
+// Drops the first `column` characters of a line
+fn skip_columns(spans: Vec<Span<'static>>, column: usize) -> Vec<Span<'static>> {
+    let mut skip = column;
+
+    spans
+        .into_iter()
+        .filter_map(|span| {
+            let count = span.content.chars().count();
+
+            if skip >= count {
+                skip -= count;
+                return None;
+            }
+
+            let start = span
+                .content
+                .char_indices()
+                .nth(skip)
+                .map_or(0, |(start, _)| start);
+            skip = 0;
+
+            Some(Span::styled(
+                String::from(&span.content[start..]),
+                span.style,
+            ))
+        })
+        .collect()
+}
+
+fn highlight<'s>(spans: Spans<'s>, theme: &Theme) -> Spans<'s> {
+    Spans::from(
+        spans
+            .0
+            .into_iter()
+            .map(|span| Span::styled(span.content, span.style.patch(theme.selected())))
+            .collect::<Vec<Span>>(),
+    )
+}
+
+// Form bodies as a table of their decoded names and values
+fn form_lines(text: &str, theme: &Theme) -> Vec<Vec<Span<'static>>> {
+    let pairs = url::form_urlencoded::parse(text.trim().as_bytes()).collect::<Vec<_>>();
+
+    let width = pairs
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or_default()
+        .min(40);
+
+    pairs
+        .into_iter()
+        .map(|(name, value)| {
+            vec![
+                Span::styled(
+                    format!("{:<width$}", name, width = width),
+                    theme.fg(theme.json_key),
+                ),
+                Span::styled(" │ ", theme.fg(theme.json_punctuation)),
+                Span::styled(value.into_owned(), theme.fg(theme.json_string)),
+            ]
+        })
+        .collect()
+}
+
+// One line of a hex dump: the offset, the bytes and their printable characters
+fn hex_line(body: &[u8], line: usize, theme: &Theme) -> Vec<Span<'static>> {
+    let start = (line * HEX_WIDTH).min(body.len());
+    let bytes = &body[start..(start + HEX_WIDTH).min(body.len())];
+
+    let mut hex = String::new();
+    for i in 0..HEX_WIDTH {
+        match bytes.get(i) {
+            Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+            None => hex.push_str("   "),
+        }
+
+        if i == HEX_WIDTH / 2 - 1 {
+            hex.push(' ');
+        }
+    }
+
+    let printable = bytes
+        .iter()
+        .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+            true => *b as char,
+            false => '.',
+        })
+        .collect::<String>();
+
+    vec![
+        Span::styled(format!("{:08x}  ", start), theme.fg(theme.muted)),
+        Span::raw(hex),
+        Span::styled(format!(" |{}|", printable), theme.fg(theme.label)),
+    ]
+}
+
+// Matches of the search in the bytes of the body, as line and byte range of the
+// hex dump. A match running over the end of a line is split in two
+fn hex_matches(body: &[u8], pattern: &str) -> Vec<(usize, Range<usize>)> {
+    let search = match regex::bytes::Regex::new(pattern) {
+        Ok(search) => search,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut matches = Vec::new();
+
+    for m in search.find_iter(body).filter(|m| !m.range().is_empty()) {
+        let mut start = m.start();
+
+        while start < m.end() {
+            let line = start / HEX_WIDTH;
+            let end = m.end().min((line + 1) * HEX_WIDTH);
+            let column = HEX_TEXT + start % HEX_WIDTH;
+
+            matches.push((line, column..column + end - start));
+            start = end;
+        }
+    }
+
+    matches
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    let plain = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if plain {
+        format!("{}.{}", parent, key)
+    } else {
+        format!(
+            "{}['{}']",
+            parent,
+            key.replace('\\', "\\\\").replace('\'', "\\'")
+        )
+    }
+}
+
+// Flattens JSON values into lines, objects and arrays get a ▾/▸ marker like
+// folders in the tree and collapse to a single line when folded
+struct JsonLines<'j> {
+    folded: &'j HashSet<String>,
+    theme: &'j Theme,
+    lines: &'j mut Vec<ViewLine>,
+}
+
+impl JsonLines<'_> {
+    fn push(&mut self, value: &Value, key: Option<&str>, path: &str, indent: usize, comma: bool) {
+        let container = match value {
+            Value::Object(obj) => Some(("{", "}", obj.len())),
+            Value::Array(arr) => Some(("[", "]", arr.len())),
+            _ => None,
+        };
+        let foldable = container.is_some_and(|(_, _, len)| len > 0);
+        let is_folded = foldable && self.folded.contains(path);
+
+        let marker = match (foldable, is_folded) {
+            (true, true) => "▸ ",
+            (true, false) => "▾ ",
+            _ => "  ",
+        };
+
+        let mut spans = vec![Span::raw(format!("{}{}", "  ".repeat(indent), marker))];
+
+        if let Some(key) = key {
+            spans.push(Span::styled(
+                serde_json::to_string(key).unwrap_or_default(),
+                self.theme.fg(self.theme.json_key),
+            ));
+            spans.push(Span::raw(": "));
+        }
+
+        let trailing = if comma { "," } else { "" };
+
+        let (open, close, len) = match container {
+            Some(container) => container,
+            None => {
+                spans.push(scalar(value, self.theme));
+                spans.push(Span::raw(trailing));
+                self.lines.push(ViewLine {
+                    spans,
+                    path: String::from(path),
+                    foldable: false,
+                });
+                return;
+            }
+        };
+
+        if len == 0 || is_folded {
+            let body = if len == 0 { "" } else { "…" };
+            spans.push(Span::raw(format!("{}{}{}", open, body, close)));
+            spans.push(Span::raw(trailing));
+
+            if is_folded {
+                let unit = if open == "{" { "keys" } else { "items" };
+                spans.push(Span::styled(
+                    format!("  {} {}", len, unit),
+                    self.theme.fg(self.theme.muted),
+                ));
+            }
+
+            self.lines.push(ViewLine {
+                spans,
+                path: String::from(path),
+                foldable,
+            });
+            return;
+        }
+
+        spans.push(Span::raw(open));
+        self.lines.push(ViewLine {
+            spans,
+            path: String::from(path),
+            foldable: true,
+        });
+
+        match value {
+            Value::Object(obj) => {
+                for (i, (k, v)) in obj.iter().enumerate() {
+                    let child = child_path(path, k);
+                    self.push(v, Some(k), &child, indent + 1, i + 1 < len);
+                }
+            }
+            Value::Array(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    let child = format!("{}[{}]", path, i);
+                    self.push(v, None, &child, indent + 1, i + 1 < len);
+                }
+            }
+            _ => {}
+        }
+
+        self.lines.push(ViewLine {
+            spans: vec![Span::raw(format!(
+                "{}  {}{}",
+                "  ".repeat(indent),
+                close,
+                trailing
+            ))],
+            path: String::from(path),
+            foldable: false,
+        });
+    }
+}
+
+fn scalar(value: &Value, theme: &Theme) -> Span<'static> {
+    let color = match value {
+        Value::String(_) => theme.json_string,
+        Value::Number(_) => theme.json_number,
+        _ => theme.json_literal,
+    };
+
+    Span::styled(value.to_string(), theme.fg(color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn text(lines: &[ViewLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let value = serde_json::json!({"id": 1, "tags": ["a", "b"], "x-y": {}});
+        let theme = Theme::default();
+        let mut lines = Vec::new();
+
+        let folded = HashSet::new();
+        let mut json_lines = JsonLines {
+            folded: &folded,
+            theme: &theme,
+            lines: &mut lines,
+        };
+        json_lines.push(&value, None, "$", 0, false);
+
+        assert_eq!(
+            text(&lines),
+            vec![
+                "▾ {",
+                "    \"id\": 1,",
+                "  ▾ \"tags\": [",
+                "      \"a\",",
+                "      \"b\"",
+                "    ],",
+                "    \"x-y\": {}",
+                "  }",
+            ]
+        );
+        assert_eq!(lines[4].path, "$.tags[1]");
+        assert_eq!(lines[6].path, "$['x-y']");
+
+        let mut folded = HashSet::new();
+        folded.insert(String::from("$.tags"));
+        let mut lines = Vec::new();
+
+        let mut json_lines = JsonLines {
+            folded: &folded,
+            theme: &theme,
+            lines: &mut lines,
+        };
+        json_lines.push(&value, None, "$", 0, false);
+
+        assert_eq!(text(&lines)[2], "  ▸ \"tags\": […],  2 items");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_paths_are_filters() {
+        let value = serde_json::json!({"a": [{"x-y": 1, "it's": [true]}], "b\\c": null});
+        let theme = Theme::default();
+        let folded = HashSet::new();
+        let mut lines = Vec::new();
+
+        let mut json_lines = JsonLines {
+            folded: &folded,
+            theme: &theme,
+            lines: &mut lines,
+        };
+        json_lines.push(&value, None, "$", 0, false);
+
+        // The path shown for the cursor picks out the value it is on
+        for line in lines.iter() {
+            let found = crate::filter::apply(&line.path, &value).unwrap();
+            assert_eq!(found.len(), 1, "{}", line.path);
+        }
+        assert_eq!(lines[4].path, "$.a[0]['it\\'s']");
+    }
+
+    #[test]
+    fn test_filter_bar() {
+        let mut viewer = ResponseViewer::new(Arc::default(), Arc::default());
+        viewer.endpoint = String::from("/root/0");
+        viewer.json = Some(serde_json::json!({"items": [{"id": 1}, {"id": 2}]}));
+        viewer.parsed = true;
+
+        let key = |code| KeyEvent::from(code);
+
+        viewer.event(key(KeyCode::Char('f')));
+        for c in ".items[].id".chars() {
+            viewer.event(key(KeyCode::Char(c)));
+        }
+
+        // Updated while typing
+        assert_eq!(
+            viewer.results,
+            Some(vec![serde_json::json!(1), serde_json::json!(2)])
+        );
+
+        // A broken filter keeps the last result
+        viewer.event(key(KeyCode::Char('[')));
+        assert!(viewer.filter_error.is_some());
+        assert_eq!(viewer.results.as_ref().map(Vec::len), Some(2));
+
+        viewer.event(key(KeyCode::Backspace));
+        viewer.event(key(KeyCode::Enter));
+        assert!(!viewer.is_editing());
+        assert_eq!(viewer.filter, ".items[].id");
+
+        viewer.event(key(KeyCode::Char('f')));
+        viewer.event(key(KeyCode::Esc));
+        viewer.event(key(KeyCode::Char('f')));
+        viewer.event(key(KeyCode::Up));
+        assert_eq!(
+            viewer.filter_box.as_ref().unwrap().lines()[0],
+            ".items[].id"
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let mut viewer = ResponseViewer::new(Arc::default(), Arc::default());
+        viewer.json = Some(serde_json::json!({"name": "abc", "other": "xabx"}));
+        viewer.rebuild();
+
+        viewer.set_search(Regex::new("ab").ok());
+        assert_eq!(viewer.match_status(), "2 matches");
+
+        assert!(viewer.next_match(true, false));
+        assert_eq!((viewer.cursor, viewer.match_status().as_str()), (1, "1/2"));
+        viewer.next_match(true, false);
+        viewer.next_match(true, false);
+        assert_eq!(viewer.cursor, 1);
+        viewer.next_match(false, false);
+        assert_eq!(viewer.cursor, 2);
+
+        let range = viewer.matches[1].1.clone();
+        let marked = mark_matches(
+            viewer.lines[2].spans.clone(),
+            &[(range, true)],
+            &Theme::default(),
+        );
+        let marked = marked
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect::<Vec<&str>>();
+        assert_eq!(marked, vec!["    ", "\"other\"", ": ", "\"x", "ab", "x\""]);
+    }
+
+    #[test]
+    fn test_large_response() {
+        let items = (0..50_000)
+            .map(|i| serde_json::json!({"id": i, "name": "item"}))
+            .collect::<Vec<Value>>();
+        let response = Response {
+            status: reqwest::StatusCode::OK,
+            headers: vec![(
+                String::from("Content-Type"),
+                String::from("application/json"),
+            )],
+            body: Bytes::from(serde_json::to_vec(&items).unwrap()),
+            metrics: Default::default(),
+        };
+        let response = Arc::new(response);
+
+        let mut viewer = ResponseViewer::new(Arc::default(), Arc::default());
+        viewer.set_response("/root/0", Some(Arc::clone(&response)));
+
+        // Shown from the body itself and only parsed once it's filtered
+        assert!(viewer.too_large.is_some());
+        assert!(viewer.lines.is_empty());
+        assert!(!viewer.parsed);
+        assert_eq!(viewer.text.as_str().as_ptr(), response.body.as_ptr());
+        assert!(viewer.line_text(0).starts_with("[{\"id\":0,"));
+
+        // Scrolled sideways a window at a time
+        viewer.width = 10;
+        viewer.height = 1;
+        viewer.event(KeyEvent::from(KeyCode::Char('>')));
+        viewer.event(KeyEvent::from(KeyCode::Char('>')));
+        assert_eq!(viewer.column, 10);
+        assert_eq!(viewer.text.window(0, viewer.column, 10).1, "name\":\"ite");
+        viewer.event(KeyEvent::from(KeyCode::Char('<')));
+        assert_eq!(viewer.column, 5);
+
+        // A small filtered result gets highlighted again
+        assert!(viewer.run_filter(".[1].id"));
+        assert!(viewer.parsed);
+        assert!(viewer.too_large.is_none());
+        assert_eq!(viewer.line_count(), 1);
+
+        // Clearing the filter goes back to the body, not a printed copy of it
+        assert!(viewer.run_filter(""));
+        assert!(matches!(viewer.view, View::Text));
+    }
+
+    #[test]
+    fn test_skip_columns() {
+        let spans = vec![Span::raw("ab"), Span::raw("żółw")];
+        let skipped = skip_columns(spans, 3)
+            .iter()
+            .map(|s| s.content.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(skipped, vec!["ółw"]);
+    }
+
+    #[test]
+    fn test_hex_line() {
+        let body = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\xff\xffabc";
+        let text = |line| {
+            hex_line(body, line, &Theme::default())
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            text(0),
+            "00000000  47 49 46 38 39 61 01 00  01 00 80 00 00 ff ff ff  |GIF89a..........|"
+        );
+        assert_eq!(
+            text(1),
+            "00000010  61 62 63                                          |abc|"
+        );
+
+        // Found in the bytes, marked on the printable characters
+        let matches = hex_matches(body, "GIF|(?-u:\\xff)+abc");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(&text(0)[matches[0].1.clone()], "GIF");
+        assert_eq!(&text(0)[matches[1].1.clone()], "...");
+        assert_eq!((matches[2].0, &text(1)[matches[2].1.clone()]), (1, "abc"));
+    }
+
+    #[test]
+    fn test_save_body() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("response.bin");
+
+        assert!(save_body(&path, b"first").unwrap().starts_with("Saved 5 B"));
+        assert!(save_body(&path, b"second")
+            .unwrap_err()
+            .ends_with("already exists"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+    }
+
+    #[test]
+    fn test_form_in_charset() {
+        let response = Response {
+            status: reqwest::StatusCode::OK,
+            headers: vec![(
+                String::from("Content-Type"),
+                String::from("application/x-www-form-urlencoded; charset=iso-8859-2"),
+            )],
+            body: Bytes::from_static(b"name=\xbf\xf3\xb3w&long+name=a%26b"),
+            metrics: Default::default(),
+        };
+
+        let mut viewer = ResponseViewer::new(Arc::default(), Arc::default());
+        viewer.set_response("/root/0", Some(Arc::new(response)));
+
+        assert!(!viewer.hex);
+        assert_eq!(
+            text(&viewer.lines),
+            vec!["name      │ żółw", "long name │ a&b"]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use regex::Regex;
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::keymap::{Keymap, Scope, Shortcut};
+use crate::theme::Theme;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchTarget {
+    Response,
+    Body,
+}
+
+// What a key pressed in the open search bar did
+#[derive(PartialEq)]
+pub enum SearchEvent {
+    Changed,
+    Submitted,
+    Cancelled,
+    Ignored,
+}
+
+pub struct SearchBar<'a> {
+    // Query being typed, only there while the bar is open
+    input: Option<TextArea<'a>>,
+
+    query: String,
+    pub target: SearchTarget,
+    ignore_case: bool,
+    regex: bool,
+
+    // Why the query isn't a valid regex
+    error: Option<String>,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+}
+
+impl<'a> SearchBar<'a> {
+    pub fn new(keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        Self {
+            input: None,
+            query: String::new(),
+            target: SearchTarget::Response,
+            ignore_case: true,
+            regex: false,
+            error: None,
+            keymap,
+            theme,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    // Shown while open and as long as there is something searched for
+    pub fn is_visible(&self) -> bool {
+        self.is_open() || !self.query.is_empty()
+    }
+
+    pub fn open(&mut self) {
+        let mut input = TextArea::from([self.query.clone()]);
+        input.move_cursor(CursorMove::End);
+        input.set_cursor_line_style(Style::default());
+
+        self.input = Some(input);
+    }
+
+    pub fn clear(&mut self) {
+        self.input = None;
+        self.query.clear();
+        self.error = None;
+    }
+
+    // None when there's nothing to search for or the regex doesn't compile
+    pub fn pattern(&mut self) -> Option<Regex> {
+        self.error = None;
+
+        if self.query.is_empty() {
+            return None;
+        }
+
+        let mut query = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+
+        // Kept in the pattern itself so the body's TextArea, which only takes
+        // a string, searches the same way
+        if self.ignore_case {
+            query.insert_str(0, "(?i)");
+        }
+
+        match Regex::new(&query) {
+            Ok(pattern) => Some(pattern),
+            Err(_) => {
+                self.error = Some(String::from("Invalid regex"));
+                None
+            }
+        }
+    }
+
+    // Switches between the response and the body, case sensitivity and regex
+    // mode with the keys from the keymap, anything else is typed
+    pub fn event(&mut self, ev: KeyEvent) -> SearchEvent {
+        let input = match &mut self.input {
+            Some(input) => input,
+            None => return SearchEvent::Ignored,
+        };
+
+        match ev.code {
+            KeyCode::Esc => {
+                self.clear();
+                return SearchEvent::Cancelled;
+            }
+            KeyCode::Enter => {
+                self.input = None;
+                return SearchEvent::Submitted;
+            }
+            _ => {}
+        }
+
+        match self.keymap.lookup(ev, Scope::SearchBar) {
+            Some(Shortcut::SearchTarget) => {
+                self.target = match self.target {
+                    SearchTarget::Response => SearchTarget::Body,
+                    SearchTarget::Body => SearchTarget::Response,
+                };
+                SearchEvent::Changed
+            }
+            Some(Shortcut::SearchCase) => {
+                self.ignore_case = !self.ignore_case;
+                SearchEvent::Changed
+            }
+            Some(Shortcut::SearchRegex) => {
+                self.regex = !self.regex;
+                SearchEvent::Changed
+            }
+            _ => {
+                if input.input(ev) {
+                    self.query = input.lines()[0].clone();
+                    SearchEvent::Changed
+                } else {
+                    SearchEvent::Ignored
+                }
+            }
+        }
+    }
+
+    // `matches` is what the current target reports, like "3/12"
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect, matches: &str) {
+        let theme = &self.theme;
+        let flag = |on: bool, name: &'static str| {
+            let style = match on {
+                true => theme.fg(theme.label).add_modifier(Modifier::BOLD),
+                false => theme.fg(theme.muted),
+            };
+            Span::styled(name, style)
+        };
+
+        let target = match self.target {
+            SearchTarget::Response => "response",
+            SearchTarget::Body => "body",
+        };
+
+        let status = match &self.error {
+            Some(e) => Span::styled(format!(" {} ", e), theme.fg(theme.error)),
+            None => Span::styled(format!(" {} ", matches), theme.fg(theme.highlight)),
+        };
+
+        let info = Spans::from(vec![
+            status,
+            Span::styled(format!("in {} ", target), theme.fg(theme.muted)),
+            flag(!self.ignore_case, "Aa"),
+            Span::raw(" "),
+            flag(self.regex, ".*"),
+        ]);
+        let info_width = info.width() as u16;
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(info_width),
+            ])
+            .split(r);
+
+        f.render_widget(Paragraph::new("/"), chunks[0]);
+
+        match &self.input {
+            Some(input) => f.render_widget(input.widget(), chunks[1]),
+            None => f.render_widget(Paragraph::new(self.query.as_str()), chunks[1]),
+        }
+
+        f.render_widget(Paragraph::new(info), chunks[2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    #[test]
+    fn test_pattern() {
+        let mut search = SearchBar::new(Arc::default(), Arc::default());
+        search.open();
+
+        for c in "a.b".chars() {
+            search.event(KeyEvent::from(KeyCode::Char(c)));
+        }
+
+        let pattern = search.pattern().unwrap();
+        assert!(pattern.is_match("xA.B"));
+        assert!(!pattern.is_match("axb"));
+
+        search.event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        search.event(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        let pattern = search.pattern().unwrap();
+        assert!(pattern.is_match("axb"));
+        assert!(!pattern.is_match("AXB"));
+
+        search.event(KeyEvent::from(KeyCode::Char('(')));
+        assert!(search.pattern().is_none());
+        assert!(search.error.is_some());
+    }
+
+    #[test]
+    fn test_remapped_keys() {
+        let keymap = Keymap::from_toml("search_regex = \"ctrl-e\"").unwrap();
+        let mut search = SearchBar::new(Arc::new(keymap), Arc::default());
+        search.open();
+
+        search.event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL));
+        assert!(search.regex);
+
+        // The old key is typed like any other now
+        search.event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert!(search.regex);
+
+        search.event(KeyEvent::from(KeyCode::Tab));
+        assert!(search.target == SearchTarget::Body);
+    }
+}
@@ -0,0 +1,7 @@
+use std::time::Duration;
+
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+pub fn spinner(elapsed: Duration) -> &'static str {
+    FRAMES[(elapsed.as_millis() / 100) as usize % FRAMES.len()]
+}
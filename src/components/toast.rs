@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::error::Error;
+use crate::theme::Theme;
+
+// Only the newest ones are kept, older ones are dropped
+const MAX_TOASTS: usize = 4;
+
+const TOAST_WIDTH: u16 = 50;
+
+// Notification shown over the top right corner until it's dismissed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub title: String,
+    pub message: String,
+}
+
+impl From<Error> for Toast {
+    fn from(e: Error) -> Self {
+        Self::from(&e)
+    }
+}
+
+impl From<&Error> for Toast {
+    fn from(e: &Error) -> Self {
+        Toast {
+            title: String::from(e.title()),
+            message: e.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    // Oldest first
+    items: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, toast: Toast) {
+        // The same error coming up again only moves it to the top
+        self.items.retain(|t| *t != toast);
+        self.items.push_back(toast);
+
+        while self.items.len() > MAX_TOASTS {
+            self.items.pop_front();
+        }
+    }
+
+    // Removes the newest toast
+    pub fn dismiss(&mut self) -> bool {
+        self.items.pop_back().is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Stacks the toasts down from the top right corner of `r`, newest first
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, r: Rect, theme: &Theme) {
+        let width = TOAST_WIDTH.min(r.width);
+        let mut y = r.y;
+
+        for toast in self.items.iter().rev() {
+            let inner_width = width.saturating_sub(2).max(1) as usize;
+            let lines = toast
+                .message
+                .lines()
+                .map(|line| line.chars().count().max(1).div_ceil(inner_width))
+                .sum::<usize>() as u16;
+
+            let height = (lines + 2).min(r.bottom().saturating_sub(y));
+            if height < 3 {
+                break;
+            }
+
+            let area = Rect::new(r.right() - width, y, width, height);
+            let title = Span::styled(
+                format!("{} [Esc]", toast.title),
+                Style::default().add_modifier(Modifier::BOLD),
+            );
+
+            let paragraph = Paragraph::new(toast.message.as_str())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.fg(theme.error))
+                        .title(title),
+                )
+                .wrap(Wrap { trim: false });
+
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+
+            y += height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts() {
+        let mut toasts = Toasts::default();
+
+        for i in 0..6 {
+            toasts.push(Toast::from(Error::Schema(format!("Item {}", i))));
+        }
+        assert_eq!(toasts.items.len(), MAX_TOASTS);
+        assert_eq!(toasts.items[0].message, "Item 2");
+        assert_eq!(toasts.items[0].title, "Collection error");
+
+        toasts.push(Toast::from(Error::Schema(String::from("Item 2"))));
+        assert_eq!(toasts.items.len(), MAX_TOASTS);
+        assert_eq!(toasts.items.back().unwrap().message, "Item 2");
+
+        assert!(toasts.dismiss());
+        assert_eq!(toasts.items.back().unwrap().message, "Item 5");
+    }
+}
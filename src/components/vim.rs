@@ -0,0 +1,209 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::Paragraph,
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::components::{Action, Component, Toast};
+use crate::keymap::{HelpSection, Keymap, Scope, Shortcut};
+
+// Keeps a typo like 99999j from spinning for a while
+const MAX_COUNT: usize = 9999;
+
+// Vim keys that don't map to a single shortcut: counts like `5j`, `gg`, `gt`
+// and `gT`, and the `:` command line, which runs shortcuts by their keymap name.
+// Everything else is passed on as it is.
+pub struct Vim<'a> {
+    keymap: Arc<Keymap>,
+    count: Option<usize>,
+
+    // `g` was typed and waits for the second key
+    prefix: bool,
+
+    // Command being typed, only there while the command line is open
+    command_line: Option<TextArea<'a>>,
+}
+
+impl Vim<'_> {
+    pub fn new(keymap: Arc<Keymap>) -> Self {
+        Self {
+            keymap,
+            count: None,
+            prefix: false,
+            command_line: None,
+        }
+    }
+
+    fn command_line_event(&mut self, ev: KeyEvent) -> Action {
+        let input = match &mut self.command_line {
+            Some(input) => input,
+            None => return Action::None,
+        };
+
+        match ev.code {
+            KeyCode::Esc => self.command_line = None,
+            KeyCode::Backspace if input.lines()[0].is_empty() => self.command_line = None,
+            KeyCode::Enter => {
+                let command = input.lines()[0].clone();
+                self.command_line = None;
+
+                return self.run(&command);
+            }
+            _ => {
+                input.input(ev);
+            }
+        }
+
+        Action::None
+    }
+
+    // `:q` and `:w` like in vim, any other command is the name of a shortcut
+    fn run(&self, command: &str) -> Action {
+        let name = match command.trim() {
+            "" => return Action::None,
+            "q" | "q!" | "qa" | "quit" => return Action::Quit,
+            "w" => "save_body",
+            name => name,
+        };
+
+        let key = Keymap::shortcut(name).and_then(|s| self.keymap.key_event(s));
+
+        match key {
+            Some(key) => Action::Repeat(key, 1),
+            None => Action::Toast(Toast {
+                title: String::from("Unknown command"),
+                message: format!("Not a command or a shortcut with a key: {}", name),
+            }),
+        }
+    }
+
+    // Keys of its own, listed in the help after the keymap's
+    pub fn help() -> HelpSection {
+        HelpSection {
+            title: "Vim",
+            keys: vec![
+                (String::from("1-9"), "Count for the next key, like 5j"),
+                (String::from("gg"), "Go to the top"),
+                (String::from("gt gT"), "Next or previous tab"),
+                (String::from(":q"), "Quit"),
+                (String::from(":w"), "Save the response body to a file"),
+                (String::from(":<name>"), "Run a shortcut by its keymap name"),
+            ],
+        }
+    }
+}
+
+impl Component for Vim<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        if self.command_line.is_some() {
+            return self.command_line_event(ev);
+        }
+
+        let count = self.count.take();
+
+        if let (KeyCode::Char(c @ '0'..='9'), KeyModifiers::NONE) = (ev.code, ev.modifiers) {
+            // A leading 0 isn't a count
+            if c != '0' || count.is_some() {
+                let digit = c as usize - '0' as usize;
+                self.count = Some((count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+                return Action::None;
+            }
+        }
+
+        if std::mem::take(&mut self.prefix) {
+            return match ev.code {
+                KeyCode::Char('g') => match self.keymap.key_event(Shortcut::Top) {
+                    Some(key) => Action::Repeat(key, 1),
+                    None => Action::None,
+                },
+                KeyCode::Char('t') => Action::NextTab,
+                KeyCode::Char('T') => Action::PreviousTab,
+                _ => Action::None,
+            };
+        }
+
+        if ev.code == KeyCode::Char('g') && ev.modifiers == KeyModifiers::NONE {
+            self.prefix = true;
+            return Action::None;
+        }
+
+        if self.keymap.lookup(ev, Scope::Global) == Some(Shortcut::CommandLine) {
+            let mut input = TextArea::default();
+            input.set_cursor_line_style(Style::default());
+            self.command_line = Some(input);
+
+            return Action::None;
+        }
+
+        Action::Repeat(ev, count.unwrap_or(1))
+    }
+
+    // Takes the place of the command bar while open
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        if let Some(input) = &self.command_line {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(r);
+
+            f.render_widget(Paragraph::new(":"), chunks[0]);
+            f.render_widget(input.widget(), chunks[1]);
+        }
+    }
+
+    fn is_editing(&self) -> bool {
+        self.command_line.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::from(KeyCode::Char(c))
+    }
+
+    #[test]
+    fn test_vim_keys() {
+        let mut vim = Vim::new(Arc::new(Keymap::vim()));
+
+        assert_eq!(vim.event(key('1')), Action::None);
+        assert_eq!(vim.event(key('2')), Action::None);
+        assert_eq!(vim.event(key('j')), Action::Repeat(key('j'), 12));
+        assert_eq!(vim.event(key('0')), Action::Repeat(key('0'), 1));
+
+        assert_eq!(vim.event(key('g')), Action::None);
+        assert_eq!(
+            vim.event(key('g')),
+            Action::Repeat(KeyEvent::from(KeyCode::Home), 1)
+        );
+        vim.event(key('g'));
+        assert_eq!(vim.event(key('T')), Action::PreviousTab);
+
+        vim.event(key(':'));
+        assert!(vim.is_editing());
+        for c in "send".chars() {
+            vim.event(key(c));
+        }
+        assert_eq!(
+            vim.event(KeyEvent::from(KeyCode::Enter)),
+            Action::Repeat(key('s'), 1)
+        );
+        assert!(!vim.is_editing());
+
+        vim.event(key(':'));
+        vim.event(key('q'));
+        assert_eq!(vim.event(KeyEvent::from(KeyCode::Enter)), Action::Quit);
+
+        vim.event(key(':'));
+        vim.event(key('x'));
+        let unknown = vim.event(KeyEvent::from(KeyCode::Enter));
+        assert!(matches!(unknown, Action::Toast(_)));
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::Local;
+use crossterm::{
+    cursor::Show,
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use std::{backtrace::Backtrace, fs, io, panic, path::PathBuf};
+
+use crate::logger;
+
+// Puts the terminal back the way it was before the app started
+pub fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, Show)
+}
+
+// On a panic anywhere, including in a request task, the terminal is restored
+// first so the message can be read at all, then a report is written and the
+// app exits
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+
+        let report = report(
+            &info.to_string(),
+            &Backtrace::force_capture().to_string(),
+            &logger::last_lines(),
+        );
+
+        match write_report(&report) {
+            Ok(path) => eprintln!("postui crashed, the report is in {}", path.display()),
+            Err(e) => eprintln!("postui crashed and the report couldn't be saved: {}", e),
+        }
+
+        default_hook(info);
+        std::process::exit(101);
+    }));
+}
+
+fn report(message: &str, backtrace: &str, logs: &[String]) -> String {
+    format!(
+        "postui {} crashed at {}\n\n{}\n\nBacktrace:\n{}\n\nLast log lines:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        message,
+        backtrace.trim_end(),
+        logs.join("\n")
+    )
+}
+
+fn write_report(report: &str) -> io::Result<PathBuf> {
+    let name = format!("postui-crash-{}.log", Local::now().format("%Y%m%d-%H%M%S"));
+    let path = std::env::temp_dir().join(name);
+
+    fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let logs = vec![String::from("12:00:00:INFO:postui:App initialized")];
+        let report = report("panicked at src/app.rs:1:1:\noops", "0: main\n", &logs);
+
+        assert!(report.starts_with(&format!("postui {} crashed at", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("\n\npanicked at src/app.rs:1:1:\noops\n\nBacktrace:\n0: main\n\n"));
+        assert!(report.ends_with("Last log lines:\n12:00:00:INFO:postui:App initialized\n"));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fmt, io};
+
+// Everything that can go wrong in the app, by where it comes from
+#[derive(Debug)]
+pub enum Error {
+    // Reading or writing a file
+    Io(io::Error),
+
+    // Text that should be JSON, a URL or a number but isn't
+    Parse(String),
+
+    // The collection is valid JSON but an item in it is missing something
+    Schema(String),
+
+    // Building, signing, sending or reading a request
+    Http(String),
+
+    // A response filter that can't be read or run
+    Script(String),
+
+    // A settings file like the keymap that can't be used
+    Config(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // Short name of the kind of error, used as the title of its toast
+    pub fn title(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "File error",
+            Error::Parse(_) => "Parse error",
+            Error::Schema(_) => "Collection error",
+            Error::Http(_) => "Request error",
+            Error::Script(_) => "Filter error",
+            Error::Config(_) => "Config error",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(msg)
+            | Error::Schema(msg)
+            | Error::Http(msg)
+            | Error::Script(msg)
+            | Error::Config(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        match e.classify() {
+            serde_json::error::Category::Io => Error::Io(e.into()),
+            _ => Error::Parse(e.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(e: hyper::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
+// The auth, proxy and decoding helpers still report plain boxed errors, all of
+// them while a request is being put together or read
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        Error::Http(e.to_string())
+    }
+}
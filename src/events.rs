@@ -0,0 +1,84 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::components::Toast;
+
+// How often the collection file is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+// Everything besides key presses that can change what's on screen. Terminal
+// events are read straight from crossterm's stream in the main loop.
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    // A request sent from the endpoint at this path is done, whatever the outcome
+    RequestFinished { endpoint: String, id: u64 },
+
+    // The collection file was changed on disk, by us or by someone else
+    CollectionChanged,
+
+    // Something went wrong away from any key press
+    Toast(Toast),
+}
+
+pub type MessageSender = UnboundedSender<Message>;
+
+// Sends `CollectionChanged` each time the modification time of the file at
+// `path` moves. The file may be missing for a while, e.g. when an editor
+// replaces it instead of writing in place.
+pub fn watch_file<P: AsRef<Path>>(path: P, tx: MessageSender) -> JoinHandle<()> {
+    let path = PathBuf::from(path.as_ref());
+
+    tokio::spawn(async move {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        let mut last: Option<SystemTime> = modified(&path);
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let current = modified(&path);
+
+            if current.is_some() && current != last {
+                last = current;
+
+                if tx.send(Message::CollectionChanged).is_err() {
+                    // Nobody is listening anymore
+                    break;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{sync::mpsc, time::timeout};
+
+    #[tokio::test]
+    async fn test_watch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, "{}").unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watcher = watch_file(&path, tx);
+
+        // Nothing changed yet
+        assert!(timeout(WATCH_INTERVAL * 2, rx.recv()).await.is_err());
+
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+
+        let message = timeout(WATCH_INTERVAL * 4, rx.recv()).await.unwrap();
+        assert_eq!(message, Some(Message::CollectionChanged));
+
+        watcher.abort();
+    }
+}
@@ -0,0 +1,887 @@
+use serde_json::Value;
+use std::cmp::Ordering;
+
+use crate::error::{self, Error};
+
+// A subset of jq: paths (.a.b, ."a-b", .[0], .[1:3], .[], ..), pipes, commas,
+// comparisons, and/or, literals and a few builtins (select, map, keys, length,
+// has, first, last, type, not). JSONPath expressions starting with `$` are
+// translated to jq first, so strings can be single quoted as well and `..name`
+// picks `name` out of every object below.
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Identity,
+    Literal(Value),
+    Field(Box<Expr>, String),
+
+    // Every value below the target, the target included, or only the `name`
+    // of the objects among them that have one
+    Recurse(Box<Expr>, Option<String>),
+
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<i64>, Option<i64>),
+    Iterate(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(String, Option<Box<Expr>>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Dot,
+    DotDot,
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Colon,
+    Pipe,
+    Comma,
+    Op(Op),
+}
+
+pub fn apply(filter: &str, input: &Value) -> error::Result<Vec<Value>> {
+    run(filter, input).map_err(Error::Script)
+}
+
+fn run(filter: &str, input: &Value) -> Result<Vec<Value>, String> {
+    let filter = filter.trim();
+
+    if filter.is_empty() {
+        return Ok(vec![input.clone()]);
+    }
+
+    let filter = if filter.starts_with('$') {
+        from_json_path(filter)
+    } else {
+        String::from(filter)
+    };
+
+    let tokens = tokenize(&filter)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.pipe()?;
+
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected {:?}", token));
+    }
+
+    eval(&expr, input)
+}
+
+// $.a.b[0][*] becomes .a.b[0][]
+fn from_json_path(path: &str) -> String {
+    let rest = path[1..].replace("[*]", "[]");
+
+    if rest.is_empty() {
+        String::from(".")
+    } else if rest.starts_with('[') {
+        format!(".{}", rest)
+    } else {
+        rest
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            ' ' | '\t' | '\n' => {}
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                tokens.push(Token::DotDot);
+                i += 1;
+            }
+            '.' => tokens.push(Token::Dot),
+            '[' => tokens.push(Token::LBracket),
+            ']' => tokens.push(Token::RBracket),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            ':' => tokens.push(Token::Colon),
+            '|' => tokens.push(Token::Pipe),
+            ',' => tokens.push(Token::Comma),
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.get(i + 1) == Some(&'=');
+                let op = match (c, eq) {
+                    ('=', true) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', true) => Op::Le,
+                    ('>', true) => Op::Ge,
+                    ('<', false) => Op::Lt,
+                    ('>', false) => Op::Gt,
+                    _ => return Err(format!("Unexpected '{}'", c)),
+                };
+                if eq {
+                    i += 1;
+                }
+                tokens.push(Token::Op(op));
+            }
+            '"' | '\'' => {
+                let mut s = String::new();
+                i += 1;
+
+                loop {
+                    match chars.get(i) {
+                        Some(q) if *q == c => break,
+                        Some('\\') => {
+                            i += 1;
+                            match chars.get(i) {
+                                Some('n') => s.push('\n'),
+                                Some('t') => s.push('\t'),
+                                Some(c) => s.push(*c),
+                                None => return Err(String::from("Unterminated string")),
+                            }
+                        }
+                        Some(c) => s.push(*c),
+                        None => return Err(String::from("Unterminated string")),
+                    }
+                    i += 1;
+                }
+
+                tokens.push(Token::Str(s));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+
+                let text = chars[start..i].iter().collect::<String>();
+                let num = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid number {}", text))?;
+
+                tokens.push(Token::Num(num));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+                continue;
+            }
+            _ => return Err(format!("Unexpected '{}'", c)),
+        }
+
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {:?}, found {:?}", expected, token)),
+            None => Err(format!("Expected {:?}", expected)),
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr, String> {
+        let left = self.comma()?;
+
+        if self.peek() == Some(&Token::Pipe) {
+            self.next();
+            return Ok(Expr::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Expr, String> {
+        let mut left = self.or()?;
+
+        while self.peek() == Some(&Token::Comma) {
+            self.next();
+            left = Expr::Comma(Box::new(left), Box::new(self.or()?));
+        }
+
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+
+        while self.peek() == Some(&Token::Ident(String::from("or"))) {
+            self.next();
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.compare()?;
+
+        while self.peek() == Some(&Token::Ident(String::from("and"))) {
+            self.next();
+            left = Expr::And(Box::new(left), Box::new(self.compare()?));
+        }
+
+        Ok(left)
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let left = self.term()?;
+
+        if let Some(Token::Op(op)) = self.peek().cloned() {
+            self.next();
+            return Ok(Expr::Compare(Box::new(left), op, Box::new(self.term()?)));
+        }
+
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut expr = match self.next() {
+            Some(Token::Dot) => match self.peek().cloned() {
+                Some(Token::Ident(name)) => {
+                    self.next();
+                    Expr::Field(Box::new(Expr::Identity), name)
+                }
+                Some(Token::Str(name)) => {
+                    self.next();
+                    Expr::Field(Box::new(Expr::Identity), name)
+                }
+                _ => Expr::Identity,
+            },
+            Some(Token::DotDot) => self.recurse(Expr::Identity)?,
+            Some(Token::Str(s)) => Expr::Literal(Value::from(s)),
+            Some(Token::Num(n)) => Expr::Literal(number(n)),
+            Some(Token::LParen) => {
+                let inner = self.pipe()?;
+                self.expect(Token::RParen)?;
+                inner
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                _ => {
+                    let arg = if self.peek() == Some(&Token::LParen) {
+                        self.next();
+                        let arg = self.pipe()?;
+                        self.expect(Token::RParen)?;
+                        Some(Box::new(arg))
+                    } else {
+                        None
+                    };
+                    Expr::Call(name, arg)
+                }
+            },
+            Some(token) => return Err(format!("Unexpected {:?}", token)),
+            None => return Err(String::from("Unexpected end of filter")),
+        };
+
+        // Postfix: .name, ."name", [..]
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Ident(name)) | Some(Token::Str(name)) => {
+                            expr = Expr::Field(Box::new(expr), name)
+                        }
+                        Some(Token::LBracket) => expr = self.bracket(expr)?,
+                        _ => return Err(String::from("Expected a field name after '.'")),
+                    }
+                }
+                Some(Token::DotDot) => {
+                    self.next();
+                    expr = self.recurse(expr)?;
+                }
+                Some(Token::LBracket) => {
+                    self.next();
+                    expr = self.bracket(expr)?;
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    // Whatever follows a `..` that was already consumed: a name right after it
+    // is the JSONPath `..name`, anything else leaves the `..` on its own
+    fn recurse(&mut self, target: Expr) -> Result<Expr, String> {
+        let target = Box::new(target);
+
+        match self.peek().cloned() {
+            Some(Token::Ident(name)) | Some(Token::Str(name)) => {
+                self.next();
+                Ok(Expr::Recurse(target, Some(name)))
+            }
+            Some(Token::LBracket) => {
+                self.next();
+                match self.bracket(Expr::Identity)? {
+                    Expr::Index(_, key) => match *key {
+                        Expr::Literal(Value::String(name)) => Ok(Expr::Recurse(target, Some(name))),
+                        _ => Err(String::from("Only names can follow '..'")),
+                    },
+                    _ => Err(String::from("Only names can follow '..'")),
+                }
+            }
+            _ => Ok(Expr::Recurse(target, None)),
+        }
+    }
+
+    // Whatever follows a '[' that was already consumed
+    fn bracket(&mut self, target: Expr) -> Result<Expr, String> {
+        let target = Box::new(target);
+
+        if self.peek() == Some(&Token::RBracket) {
+            self.next();
+            return Ok(Expr::Iterate(target));
+        }
+
+        let start = if self.peek() == Some(&Token::Colon) {
+            None
+        } else {
+            Some(self.pipe()?)
+        };
+
+        if self.peek() == Some(&Token::Colon) {
+            self.next();
+
+            let end = if self.peek() == Some(&Token::RBracket) {
+                None
+            } else {
+                Some(self.pipe()?)
+            };
+            self.expect(Token::RBracket)?;
+
+            return Ok(Expr::Slice(target, slice_bound(start)?, slice_bound(end)?));
+        }
+
+        self.expect(Token::RBracket)?;
+
+        match start {
+            Some(index) => Ok(Expr::Index(target, Box::new(index))),
+            None => Err(String::from("Empty index")),
+        }
+    }
+}
+
+fn slice_bound(expr: Option<Expr>) -> Result<Option<i64>, String> {
+    match expr {
+        None => Ok(None),
+        Some(Expr::Literal(Value::Number(n))) => integer(&n).map(Some),
+        Some(_) => Err(String::from("Slice bounds must be numbers")),
+    }
+}
+
+// Indices are whole numbers, 1.5 is an error rather than quietly becoming 1
+fn integer(n: &serde_json::Number) -> Result<i64, String> {
+    match n.as_i64() {
+        Some(i) => Ok(i),
+        None => Err(format!("{} is not a valid index", n)),
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+// jq orders null < false < true < numbers < strings < arrays < objects
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    let rank = |v: &Value| match v {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y.iter())
+            .map(|(x, y)| compare_values(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        _ if a == b => Ordering::Equal,
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn index_array(arr: &[Value], index: i64) -> Value {
+    let index = if index < 0 {
+        arr.len() as i64 + index
+    } else {
+        index
+    };
+
+    if index < 0 {
+        return Value::Null;
+    }
+
+    arr.get(index as usize).cloned().unwrap_or(Value::Null)
+}
+
+fn eval(expr: &Expr, input: &Value) -> Result<Vec<Value>, String> {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(target, name) => eval(target, input)?
+            .iter()
+            .map(|value| match value {
+                Value::Object(obj) => Ok(obj.get(name).cloned().unwrap_or(Value::Null)),
+                Value::Null => Ok(Value::Null),
+                other => Err(format!(
+                    "Cannot index {} with \"{}\"",
+                    type_name(other),
+                    name
+                )),
+            })
+            .collect(),
+        Expr::Index(target, index) => {
+            let mut out = Vec::new();
+
+            for value in eval(target, input)? {
+                for index in eval(index, input)? {
+                    out.push(match (&value, &index) {
+                        (Value::Array(arr), Value::Number(n)) => index_array(arr, integer(n)?),
+                        (Value::Object(obj), Value::String(key)) => {
+                            obj.get(key).cloned().unwrap_or(Value::Null)
+                        }
+                        (Value::Null, _) => Value::Null,
+                        _ => {
+                            return Err(format!(
+                                "Cannot index {} with {}",
+                                type_name(&value),
+                                type_name(&index)
+                            ))
+                        }
+                    });
+                }
+            }
+
+            Ok(out)
+        }
+        Expr::Slice(target, start, end) => eval(target, input)?
+            .iter()
+            .map(|value| {
+                let len = match value {
+                    Value::Array(arr) => arr.len(),
+                    Value::String(s) => s.chars().count(),
+                    Value::Null => return Ok(Value::Null),
+                    other => return Err(format!("Cannot slice {}", type_name(other))),
+                } as i64;
+
+                let clamp = |i: i64| (if i < 0 { len + i } else { i }).clamp(0, len) as usize;
+                let from = clamp(start.unwrap_or(0));
+                let to = clamp(end.unwrap_or(len)).max(from);
+
+                Ok(match value {
+                    Value::Array(arr) => Value::Array(arr[from..to].to_vec()),
+                    Value::String(s) => {
+                        Value::from(s.chars().skip(from).take(to - from).collect::<String>())
+                    }
+                    _ => Value::Null,
+                })
+            })
+            .collect(),
+        Expr::Recurse(target, name) => {
+            let mut out = Vec::new();
+
+            for value in eval(target, input)? {
+                descendants(&value, name.as_deref(), &mut out);
+            }
+
+            Ok(out)
+        }
+        Expr::Iterate(target) => {
+            let mut out = Vec::new();
+
+            for value in eval(target, input)? {
+                match value {
+                    Value::Array(arr) => out.extend(arr),
+                    Value::Object(obj) => out.extend(obj.into_iter().map(|(_, v)| v)),
+                    other => return Err(format!("Cannot iterate over {}", type_name(&other))),
+                }
+            }
+
+            Ok(out)
+        }
+        Expr::Pipe(left, right) => {
+            let mut out = Vec::new();
+
+            for value in eval(left, input)? {
+                out.extend(eval(right, &value)?);
+            }
+
+            Ok(out)
+        }
+        Expr::Comma(left, right) => {
+            let mut out = eval(left, input)?;
+            out.extend(eval(right, input)?);
+            Ok(out)
+        }
+        Expr::Compare(left, op, right) => {
+            let mut out = Vec::new();
+
+            for r in eval(right, input)? {
+                for l in eval(left, input)? {
+                    let ord = compare_values(&l, &r);
+                    let result = match op {
+                        Op::Eq => ord == Ordering::Equal,
+                        Op::Ne => ord != Ordering::Equal,
+                        Op::Lt => ord == Ordering::Less,
+                        Op::Le => ord != Ordering::Greater,
+                        Op::Gt => ord == Ordering::Greater,
+                        Op::Ge => ord != Ordering::Less,
+                    };
+                    out.push(Value::Bool(result));
+                }
+            }
+
+            Ok(out)
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let is_and = matches!(expr, Expr::And(_, _));
+            let mut out = Vec::new();
+
+            for l in eval(left, input)? {
+                // Short-circuits like jq does
+                if truthy(&l) != is_and {
+                    out.push(Value::Bool(!is_and));
+                    continue;
+                }
+
+                for r in eval(right, input)? {
+                    out.push(Value::Bool(truthy(&r)));
+                }
+            }
+
+            Ok(out)
+        }
+        Expr::Call(name, arg) => call(name, arg.as_deref(), input),
+    }
+}
+
+// Depth first, a value before its children, like jq's `..`
+fn descendants(value: &Value, name: Option<&str>, out: &mut Vec<Value>) {
+    match (name, value) {
+        (None, _) => out.push(value.clone()),
+        (Some(name), Value::Object(obj)) => out.extend(obj.get(name).cloned()),
+        _ => {}
+    }
+
+    match value {
+        Value::Array(arr) => arr.iter().for_each(|v| descendants(v, name, out)),
+        Value::Object(obj) => obj.values().for_each(|v| descendants(v, name, out)),
+        _ => {}
+    }
+}
+
+fn call(name: &str, arg: Option<&Expr>, input: &Value) -> Result<Vec<Value>, String> {
+    let arg = || arg.ok_or_else(|| format!("{} needs an argument", name));
+
+    match name {
+        "select" => {
+            let keep = eval(arg()?, input)?.iter().any(truthy);
+            Ok(if keep { vec![input.clone()] } else { vec![] })
+        }
+        "map" => match input {
+            Value::Array(arr) => {
+                let mut out = Vec::new();
+                for value in arr {
+                    out.extend(eval(arg()?, value)?);
+                }
+                Ok(vec![Value::Array(out)])
+            }
+            other => Err(format!("Cannot map over {}", type_name(other))),
+        },
+        "has" => eval(arg()?, input)?
+            .iter()
+            .map(|key| match (input, key) {
+                (Value::Object(obj), Value::String(key)) => Ok(Value::Bool(obj.contains_key(key))),
+                (Value::Array(arr), Value::Number(n)) => match integer(n)? {
+                    i if i < 0 => Err(format!("{} is not a valid index", i)),
+                    i => Ok(Value::Bool((i as u64) < arr.len() as u64)),
+                },
+                _ => Err(format!(
+                    "Cannot check whether {} has a key",
+                    type_name(input)
+                )),
+            })
+            .collect(),
+        "keys" => match input {
+            Value::Object(obj) => {
+                let mut keys = obj.keys().cloned().collect::<Vec<String>>();
+                keys.sort();
+                Ok(vec![Value::from(keys)])
+            }
+            Value::Array(arr) => Ok(vec![Value::from((0..arr.len()).collect::<Vec<usize>>())]),
+            other => Err(format!("{} has no keys", type_name(other))),
+        },
+        "length" => Ok(vec![match input {
+            Value::Null => Value::from(0),
+            Value::Bool(_) => return Err(String::from("boolean has no length")),
+            Value::Number(n) => Value::from(n.as_f64().unwrap_or(0.0).abs()),
+            Value::String(s) => Value::from(s.chars().count()),
+            Value::Array(arr) => Value::from(arr.len()),
+            Value::Object(obj) => Value::from(obj.len()),
+        }]),
+        "first" => Ok(vec![match input {
+            Value::Array(arr) => index_array(arr, 0),
+            _ => Value::Null,
+        }]),
+        "last" => Ok(vec![match input {
+            Value::Array(arr) => index_array(arr, -1),
+            _ => Value::Null,
+        }]),
+        "type" => Ok(vec![Value::from(type_name(input))]),
+        "not" => Ok(vec![Value::Bool(!truthy(input))]),
+        _ => Err(format!("Unknown function {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn input() -> Value {
+        json!({
+            "data": [
+                {"id": 1, "name": "a", "tags": ["x"]},
+                {"id": 2, "name": "b", "tags": []},
+                {"id": 3, "name": "c", "tags": ["x", "y"]}
+            ],
+            "meta-info": {"total": 3}
+        })
+    }
+
+    #[test]
+    fn test_paths() {
+        let input = input();
+
+        assert_eq!(apply(".data[0].name", &input).unwrap(), vec![json!("a")]);
+        assert_eq!(apply(".data[-1].id", &input).unwrap(), vec![json!(3)]);
+        assert_eq!(
+            apply(".\"meta-info\".total", &input).unwrap(),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            apply(".data[].id", &input).unwrap(),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(
+            apply(".data[1:] | map(.id)", &input).unwrap(),
+            vec![json!([2, 3])]
+        );
+        assert_eq!(apply("$.data[*].name", &input).unwrap().len(), 3);
+        assert_eq!(apply("", &input).unwrap(), vec![input.clone()]);
+    }
+
+    #[test]
+    fn test_select_and_builtins() {
+        let input = input();
+
+        assert_eq!(
+            apply(
+                ".data[] | select(.id >= 2 and (.tags | length) > 0) | .name",
+                &input
+            )
+            .unwrap(),
+            vec![json!("c")]
+        );
+        assert_eq!(
+            apply(".data[0] | keys", &input).unwrap(),
+            vec![json!(["id", "name", "tags"])]
+        );
+        assert_eq!(
+            apply(".data | length, (first | .id)", &input).unwrap(),
+            vec![json!(3), json!(1)]
+        );
+        assert_eq!(
+            apply(".data[] | select(.name == \"b\" or .id == 1) | .id", &input).unwrap(),
+            vec![json!(1), json!(2)]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let input = input();
+
+        assert!(apply(".data.name", &input).is_err());
+        assert!(apply(".data[", &input).is_err());
+        assert!(apply("nope", &input).is_err());
+        assert!(apply(".\"unterminated", &input).is_err());
+        assert!(apply(".data[0] = 1", &input).is_err());
+        assert!(apply(".data | map", &input).is_err());
+        assert!(apply(".data[0].id[]", &input).is_err());
+        assert!(apply(".data[0] |", &input).is_err());
+    }
+
+    #[test]
+    fn test_json_path() {
+        let input = input();
+
+        assert_eq!(apply("$", &input).unwrap(), vec![input.clone()]);
+        assert_eq!(
+            apply("$['meta-info'].total", &input).unwrap(),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            apply("$[\"meta-info\"]['total']", &input).unwrap(),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            apply("$.data[2].tags[1]", &input).unwrap(),
+            vec![json!("y")]
+        );
+        assert_eq!(
+            apply("$.data[*].id", &input).unwrap(),
+            vec![json!(1), json!(2), json!(3)]
+        );
+
+        let quoted = json!({"it's": {"a\\b": true}});
+        assert_eq!(
+            apply("$['it\\'s']['a\\\\b']", &quoted).unwrap(),
+            vec![json!(true)]
+        );
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        let input = input();
+
+        assert_eq!(
+            apply("$..name", &input).unwrap(),
+            vec![json!("a"), json!("b"), json!("c")]
+        );
+        assert_eq!(apply("$..['total']", &input).unwrap(), vec![json!(3)]);
+        assert_eq!(apply("$.data[0]..id", &input).unwrap(), vec![json!(1)]);
+        assert!(apply("$..missing", &input).unwrap().is_empty());
+        assert!(apply("$..[0]", &input).is_err());
+
+        // jq's own `..`, every value with the containers before their children
+        assert_eq!(
+            apply(".data[1] | ..", &input).unwrap(),
+            vec![
+                json!({"id": 2, "name": "b", "tags": []}),
+                json!(2),
+                json!("b"),
+                json!([])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indices() {
+        let input = input();
+
+        assert_eq!(apply(".data[-2].id", &input).unwrap(), vec![json!(2)]);
+        assert_eq!(apply(".data[5]", &input).unwrap(), vec![Value::Null]);
+        assert_eq!(apply(".data[1.0].id", &input).unwrap(), vec![json!(2)]);
+        assert!(apply(".data[1.5]", &input).is_err());
+        assert!(apply(".data[0.5:]", &input).is_err());
+        assert_eq!(
+            apply(".data[-2:] | map(.id)", &input).unwrap(),
+            vec![json!([2, 3])]
+        );
+        assert_eq!(
+            apply(".data[0].name[0:1]", &input).unwrap(),
+            vec![json!("a")]
+        );
+
+        assert_eq!(apply(".data | has(2)", &input).unwrap(), vec![json!(true)]);
+        assert_eq!(apply(".data | has(3)", &input).unwrap(), vec![json!(false)]);
+        assert!(apply(".data | has(-1)", &input).is_err());
+        assert!(apply(".data | has(0.5)", &input).is_err());
+        assert_eq!(apply("has(\"data\")", &input).unwrap(), vec![json!(true)]);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let values = json!([null, false, true, 1, "a", [1], {"a": 1}]);
+
+        // Every value sorts after the ones before it in jq's order
+        for i in 1..7 {
+            let filter = format!(".[{}] > .[{}]", i, i - 1);
+            assert_eq!(apply(&filter, &values).unwrap(), vec![json!(true)]);
+        }
+
+        assert_eq!(
+            apply("1 != 2, 2 <= 2, \"b\" < \"a\"", &values).unwrap(),
+            vec![json!(true), json!(true), json!(false)]
+        );
+        assert_eq!(
+            apply("(false and nope), (true or nope)", &values).unwrap(),
+            vec![json!(false), json!(true)]
+        );
+        assert_eq!(
+            apply(".[] | type", &json!([null, 1, {}])).unwrap(),
+            vec![json!("null"), json!("number"), json!("object")]
+        );
+        assert_eq!(
+            apply(".[1:] | map(not)", &values).unwrap(),
+            vec![json!([true, false, false, false, false, false])]
+        );
+    }
+}
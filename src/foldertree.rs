@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::{cell::RefCell, error::Error, fs, path::Path};
+use std::{cell::RefCell, collections::BTreeMap, fs, path::Path};
+
+use crate::error::{Error, Result};
+use crate::fuzzy::fuzzy_match;
+use crate::http::{variables::substitute_value, Auth, ClientSettings};
 
 #[derive(Clone, Serialize)]
 pub struct Item {
@@ -12,6 +16,40 @@ pub struct Item {
 
     // A string path in order to access a specific Item in a Tree
     pub obj_ref: String,
+
+    // Characters of `rep` matched by the tree filter
+    #[serde(skip)]
+    pub highlight: Vec<usize>,
+}
+
+// Values an item inherits from the collection and its parent folders, the
+// closest ones overriding those further up
+#[derive(Clone, Default)]
+pub struct Defaults {
+    pub base_url: Option<String>,
+    pub headers: Vec<(String, String)>,
+
+    // Auth together with the path of the folder it was defined on
+    pub auth: Option<(String, Auth)>,
+
+    pub variables: BTreeMap<String, String>,
+
+    pub settings: ClientSettings,
+
+    // Name of the active environment, if the collection has one
+    pub environment: Option<String>,
+}
+
+// An endpoint anywhere in the collection, folded away or not
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointEntry {
+    pub path: String,
+    pub name: String,
+    pub method: String,
+    pub url: String,
+
+    // Names of the folders it's in, starting from the top one
+    pub folders: Vec<String>,
 }
 
 pub struct FolderTree {
@@ -25,16 +63,6 @@ pub struct FolderTree {
     path: String,
 }
 
-// This is used only to serialize new folder which will be inserted
-#[derive(Deserialize, Serialize)]
-struct NewFolder {
-    r#type: String,
-    name: String,
-    folded: bool,
-    path: String,
-    items: Vec<String>,
-}
-
 #[derive(Deserialize, Serialize)]
 struct NewEndpoint {
     r#type: String,
@@ -46,12 +74,12 @@ struct NewEndpoint {
 }
 
 impl FolderTree {
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let input = fs::read_to_string(path.as_ref())?;
         let raw_data = serde_json::from_str(input.as_str())?;
         let items = RefCell::new(Vec::new());
 
-        let path = String::from(path.as_ref().to_str().unwrap());
+        let path = path.as_ref().to_string_lossy().into_owned();
 
         Ok(FolderTree {
             items,
@@ -60,172 +88,445 @@ impl FolderTree {
         })
     }
 
-    pub fn parse_all(&self) {
-        // Each time we re-parse everything we have to clear the items vector
-        self.items.borrow_mut().clear();
+    // Stand-in for a collection that couldn't be loaded. Nothing is written to
+    // `path` until the file is fixed and reloaded.
+    pub fn empty<P: AsRef<Path>>(path: P) -> Self {
+        FolderTree {
+            items: RefCell::new(Vec::new()),
+            raw_data: serde_json::json!({ "root": [] }),
+            path: path.as_ref().to_string_lossy().into_owned(),
+        }
+    }
+
+    // Reads the collection file again after it was changed outside the app. A
+    // file that can't be parsed is left alone, it's most likely half-written.
+    pub fn reload(&mut self) -> Result<()> {
+        let input = fs::read_to_string(&self.path)?;
+        let raw_data: Value = serde_json::from_str(input.as_str())?;
 
-        let json_data = self.raw_data.get("root").and_then(Value::as_array).unwrap();
+        let previous = std::mem::replace(&mut self.raw_data, raw_data);
+
+        if let Err(e) = self.parse_all() {
+            self.raw_data = previous;
+            self.parse_all()?;
+
+            return Err(e);
+        }
 
-        self.parse(json_data, 0);
+        Ok(())
     }
 
-    fn parse(&self, json_data: &[Value], indent: i32) {
-        let mut temp_vec: Vec<Item> = Vec::new();
+    pub fn parse_all(&self) -> Result<()> {
+        // Each time we re-parse everything we have to clear the items vector
+        self.items.borrow_mut().clear();
+
+        let json_data = self
+            .raw_data
+            .get("root")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Schema(String::from("The collection has no root list")))?;
 
-        for data in json_data.iter() {
-            let val = Value::deserialize(data).unwrap();
+        self.parse(json_data, 0)
+    }
 
-            match val.get("type").and_then(Value::as_str).unwrap() {
+    fn parse(&self, json_data: &[Value], indent: i32) -> Result<()> {
+        for val in json_data.iter() {
+            match str_field(val, "type")? {
                 "endpoint" => {
-                    self.parse_endpoint(&val, indent);
+                    self.parse_endpoint(val, indent)?;
                 }
                 "folder" => {
-                    let is_folded = val.get("folded").and_then(Value::as_bool).unwrap();
-                    let indented = construct_indent(indent);
-                    let name = val.get("name").and_then(Value::as_str).unwrap();
-
-                    let symbol = if is_folded { "▸" } else { "▾" };
+                    let is_folded = val.get("folded").and_then(Value::as_bool).unwrap_or(false);
 
-                    let new_item = Item {
-                        rep: format!("{}{} 📁 {}", indented, symbol, name),
-                        r#type: val.get("type").and_then(Value::as_str).unwrap().to_string(),
-                        obj_ref: val.get("path").and_then(Value::as_str).unwrap().to_string(),
-                    };
-
-                    temp_vec.push(new_item);
-
-                    self.items.borrow_mut().append(&mut temp_vec);
+                    self.items
+                        .borrow_mut()
+                        .push(folder_item(val, is_folded, indent)?);
 
                     if !is_folded {
-                        self.parse_folder(&val, false, indent + 1);
+                        self.parse_folder(val, false, indent + 1)?;
                     }
                 }
                 _ => {}
             }
         }
-    }
 
-    pub fn parse_endpoint(&self, val: &Value, indent: i32) {
-        let cur_endpoint: NewEndpoint = serde_json::from_value(val.clone()).unwrap();
+        Ok(())
+    }
 
+    pub fn parse_endpoint(&self, val: &Value, indent: i32) -> Result<()> {
         let ind = construct_indent(indent);
 
         let temp_obj: Item = Item {
-            rep: format!("{}  {} {}", ind, cur_endpoint.method, cur_endpoint.name),
+            rep: format!(
+                "{}  {} {}",
+                ind,
+                str_field(val, "method")?,
+                str_field(val, "name")?
+            ),
             r#type: String::from("endpoint"),
-            obj_ref: val.get("path").and_then(Value::as_str).unwrap().to_string(),
+            obj_ref: str_field(val, "path")?.to_string(),
+            highlight: Vec::new(),
         };
 
         self.items.borrow_mut().push(temp_obj);
+
+        Ok(())
+    }
+
+    // Like `parse_all`, but only with the endpoints matching `query` by method,
+    // name or URL, and the folders they're in shown unfolded
+    pub fn parse_filtered(&self, query: &str) -> Result<()> {
+        self.items.borrow_mut().clear();
+
+        let json_data = self
+            .raw_data
+            .get("root")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Schema(String::from("The collection has no root list")))?;
+
+        self.parse_matching(json_data, query, 0)?;
+
+        Ok(())
+    }
+
+    // Whether anything in `json_data` matched
+    fn parse_matching(&self, json_data: &[Value], query: &str, indent: i32) -> Result<bool> {
+        let mut matched = false;
+
+        for val in json_data.iter() {
+            match str_field(val, "type")? {
+                "endpoint" => {
+                    let label =
+                        format!("{} {}", str_field(val, "method")?, str_field(val, "name")?);
+                    let url = val.get("url").and_then(Value::as_str).unwrap_or_default();
+
+                    // Only the method and name are shown, so a URL match has
+                    // nothing to highlight
+                    let highlight = match (fuzzy_match(query, &label), fuzzy_match(query, url)) {
+                        (Some(m), _) => m.positions,
+                        (None, Some(_)) => Vec::new(),
+                        (None, None) => continue,
+                    };
+
+                    let ind = construct_indent(indent);
+                    let offset = ind.chars().count() + 2;
+
+                    self.items.borrow_mut().push(Item {
+                        rep: format!("{}  {}", ind, label),
+                        r#type: String::from("endpoint"),
+                        obj_ref: str_field(val, "path")?.to_string(),
+                        highlight: highlight.iter().map(|i| i + offset).collect(),
+                    });
+
+                    matched = true;
+                }
+                "folder" => {
+                    let at = self.items.borrow().len();
+                    self.items
+                        .borrow_mut()
+                        .push(folder_item(val, false, indent)?);
+
+                    let children = val.get("items").and_then(Value::as_array).ok_or_else(|| {
+                        Error::Schema(format!("{} has no items list", describe(val)))
+                    })?;
+
+                    // Folders without a match go away again
+                    if self.parse_matching(children, query, indent + 1)? {
+                        matched = true;
+                    } else {
+                        self.items.borrow_mut().truncate(at);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(matched)
     }
 
     // Find the closest folder that we can insert out new thing into
-    fn find_closest_folder(&self, path: &str) -> String {
-        let mut is_folder = false;
+    fn find_closest_folder(&self, path: &str) -> Result<String> {
         let mut split = path.split('/').collect::<Vec<&str>>();
-        let mut ptr = self.raw_data.pointer(path).unwrap();
 
-        while is_folder == false {
-            if ptr.get("items").is_some() {
-                is_folder = true;
+        while split.len() > 1 {
+            let ptr = self.raw_data.pointer(split.join("/").as_str());
+
+            if ptr.is_some_and(|p| p.get("items").is_some()) {
+                return Ok(split.join("/"));
+            }
+
+            split.pop();
+        }
+
+        Err(Error::Schema(format!("{} isn't inside a folder", path)))
+    }
+
+    // Paths of all folders containing the given item, starting from the top one
+    fn parent_folders(&self, path: &str) -> Vec<String> {
+        let split = path.split('/').collect::<Vec<&str>>();
+
+        // Folder paths look like "/root/0", "/root/0/items/2" and so on
+        (3..split.len())
+            .step_by(2)
+            .map(|len| split[..len].join("/"))
+            .filter(|p| {
+                self.raw_data
+                    .pointer(p)
+                    .is_some_and(|v| v.get("items").is_some())
+            })
+            .collect()
+    }
+
+    // Walk from the collection down to the item and collect everything it
+    // inherits. Endpoints can override the inherited values themselves.
+    pub fn inherited_defaults(&self, path: &str) -> Defaults {
+        let mut layers = vec![String::from("")];
+
+        // The active environment sits right below the collection, so folders
+        // and endpoints can still override what it sets
+        let environment = self
+            .raw_data
+            .get("active_environment")
+            .and_then(Value::as_str)
+            .map(String::from);
+
+        if let Some(name) = &environment {
+            let layer = format!(
+                "/environments/{}",
+                name.replace('~', "~0").replace('/', "~1")
+            );
+
+            if self.raw_data.pointer(&layer).is_some() {
+                layers.push(layer);
             } else {
-                split.remove(split.len() - 1);
-                ptr = self.raw_data.pointer(split.join("/").as_str()).unwrap();
+                log::error!("Environment {} not found", name);
+            }
+        }
+
+        layers.append(&mut self.parent_folders(path));
+
+        if self.current_endpoint(path).is_some() {
+            layers.push(String::from(path));
+        }
+
+        let mut defaults = Defaults {
+            environment,
+            ..Defaults::default()
+        };
+        let mut auth = None;
+
+        for layer in layers.iter() {
+            let val = match self.raw_data.pointer(layer) {
+                Some(val) => val,
+                None => continue,
+            };
+
+            if let Some(base_url) = val.get("base_url").and_then(Value::as_str) {
+                defaults.base_url = Some(String::from(base_url));
+            }
+
+            // Headers keep the order they're written in. One that's set
+            // again replaces the parent's where it was, new ones go last
+            if let Some(headers) = val.get("headers").and_then(Value::as_object) {
+                for (name, value) in headers {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), String::from);
+
+                    match defaults
+                        .headers
+                        .iter_mut()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    {
+                        Some(header) => *header = (name.clone(), value),
+                        None => defaults.headers.push((name.clone(), value)),
+                    }
+                }
+            }
+
+            if let Some(variables) = val.get("variables").and_then(Value::as_object) {
+                for (name, value) in variables {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), String::from);
+
+                    defaults.variables.insert(name.clone(), value);
+                }
+            }
+
+            if let Some(val) = val.get("auth") {
+                auth = Some((layer.clone(), val.clone()));
+            }
+
+            if let Some(val) = val.get("settings") {
+                match serde_json::from_value::<ClientSettings>(val.clone()) {
+                    Ok(settings) => defaults.settings = defaults.settings.merge(&settings),
+                    Err(e) => log::error!("Invalid settings in {}: {}", layer, e),
+                }
             }
         }
 
-        split.join("/")
+        // Auth is only parsed once all the variables it might use are known
+        defaults.auth = auth.and_then(|(owner, val)| {
+            match serde_json::from_value(substitute_value(&val, &defaults.variables)) {
+                Ok(auth) => {
+                    let scope = if owner.is_empty() { "/" } else { &owner };
+                    Some((String::from(scope), auth))
+                }
+                Err(e) => {
+                    log::error!("Invalid auth in {}: {}", owner, e);
+                    None
+                }
+            }
+        });
+
+        defaults
     }
 
-    fn build_new_path(&self, path: &str) -> String {
+    fn build_new_path(&self, path: &str) -> Result<String> {
         let mut split: Vec<&str> = path.split('/').collect();
 
-        let new_split = split[split.len() - 1];
+        let new_split = split.pop().unwrap_or_default();
 
-        let parsed = new_split.parse::<i32>().unwrap();
+        let parsed = new_split
+            .parse::<i32>()
+            .map_err(|_| Error::Parse(format!("{} doesn't end with an index", path)))?;
 
         let parsed_plusone = (parsed + 1).to_string();
 
-        split.remove(split.len() - 1);
         split.push(parsed_plusone.as_str());
 
-        split.join("/")
+        Ok(split.join("/"))
     }
 
     pub fn current_endpoint(&self, path: &str) -> Option<Map<String, Value>> {
-        if let Some(current) = self.raw_data.pointer(path) {
-            if current.get("type") == Some(&Value::String(format!("endpoint"))) {
-                return Some(current.as_object().unwrap().to_owned());
-            }
-            None
-        } else {
-            None
+        let current = self.raw_data.pointer(path)?;
+
+        match current.get("type").and_then(Value::as_str) {
+            Some("endpoint") => current.as_object().cloned(),
+            _ => None,
         }
     }
 
-    pub fn can_fold_folder(&self, path: &str) -> bool {
-        let check = self.raw_data.pointer(path).unwrap().as_object().unwrap();
+    // Path of an endpoint given either its path or its name
+    pub fn find_endpoint(&self, query: &str) -> Option<String> {
+        if self.current_endpoint(query).is_some() {
+            return Some(String::from(query));
+        }
 
-        if check["type"] == Value::String(String::from("folder"))
-            && check["folded"] == Value::Bool(false)
-        {
-            return true;
+        fn find(items: &[Value], name: &str) -> Option<String> {
+            items.iter().find_map(|item| match item.get("items") {
+                Some(Value::Array(children)) => find(children, name),
+                _ if item.get("name").and_then(Value::as_str) == Some(name) => {
+                    item.get("path").and_then(Value::as_str).map(String::from)
+                }
+                _ => None,
+            })
         }
 
-        false
+        find(self.raw_data.get("root")?.as_array()?, query)
     }
 
-    pub fn can_unfold_folder(&self, path: &str) -> bool {
-        let check = self.raw_data.pointer(path).unwrap().as_object().unwrap();
+    // Every endpoint in the order they appear in the tree
+    pub fn endpoints(&self) -> Vec<EndpointEntry> {
+        fn walk(items: &[Value], folders: &mut Vec<String>, out: &mut Vec<EndpointEntry>) {
+            for item in items {
+                let field = |name| item.get(name).and_then(Value::as_str).unwrap_or_default();
+
+                match item.get("items").and_then(Value::as_array) {
+                    Some(children) => {
+                        folders.push(String::from(field("name")));
+                        walk(children, folders, out);
+                        folders.pop();
+                    }
+                    None if field("type") == "endpoint" => out.push(EndpointEntry {
+                        path: String::from(field("path")),
+                        name: String::from(field("name")),
+                        method: String::from(field("method")),
+                        url: String::from(field("url")),
+                        folders: folders.clone(),
+                    }),
+                    None => {}
+                }
+            }
+        }
 
-        if check["type"] == Value::String(String::from("folder"))
-            && check["folded"] == Value::Bool(true)
-        {
-            return true;
+        let mut out = Vec::new();
+        if let Some(root) = self.raw_data.get("root").and_then(Value::as_array) {
+            walk(root, &mut Vec::new(), &mut out);
         }
 
-        false
+        out
     }
 
-    pub fn fold_folder(&mut self, path: &str) {
-        // do not unwrap but check if its actually a folder
-        let check = self
-            .raw_data
-            .pointer_mut(path)
-            .unwrap()
-            .as_object_mut()
-            .unwrap();
+    // Unfolds the folders around the item so it shows up in the list
+    pub fn reveal(&mut self, path: &str) -> Result<()> {
+        for folder in self.parent_folders(path) {
+            if let Some(folder) = self.raw_data.pointer_mut(&folder) {
+                folder["folded"] = Value::Bool(false);
+            }
+        }
+
+        self.parse_all()
+    }
+
+    // Whether the item at `path` is a folder that's folded as asked
+    fn is_folder(&self, path: &str, folded: bool) -> bool {
+        self.raw_data.pointer(path).is_some_and(|item| {
+            item.get("type").and_then(Value::as_str) == Some("folder")
+                && item.get("folded").and_then(Value::as_bool).unwrap_or(false) == folded
+        })
+    }
+
+    pub fn can_unfold_folder(&self, path: &str) -> bool {
+        self.is_folder(path, true)
+    }
+
+    pub fn fold_folder(&mut self, path: &str) -> Result<()> {
+        self.set_folded(path, true)
+    }
 
-        if check["type"] == Value::String(String::from("folder"))
-            && check["folded"] == Value::Bool(false)
-        {
-            check["folded"] = Value::Bool(true);
+    pub fn unfold_folder(&mut self, path: &str) -> Result<()> {
+        self.set_folded(path, false)
+    }
+
+    fn set_folded(&mut self, path: &str, folded: bool) -> Result<()> {
+        if !self.is_folder(path, !folded) {
+            return Ok(());
+        }
 
-            self.parse_all();
+        if let Some(folder) = self.raw_data.pointer_mut(path) {
+            folder["folded"] = Value::Bool(folded);
         }
+
+        self.parse_all()
     }
 
-    pub fn unfold_folder(&mut self, path: &str) {
-        // do not unwrap but check if its actually a folder
-        let check = self
-            .raw_data
-            .pointer_mut(path)
-            .unwrap()
-            .as_object_mut()
-            .unwrap();
+    pub fn set_method(&mut self, path: &str, method: &str) -> Result<()> {
+        self.set_field(path, "method", method)
+    }
+
+    pub fn set_body(&mut self, path: &str, body: &str) -> Result<()> {
+        self.set_field(path, "json_body", body)
+    }
 
-        if check["type"] == Value::String(String::from("folder"))
-            && check["folded"] == Value::Bool(true)
-        {
-            check["folded"] = Value::Bool(false);
+    // Changes a field of the endpoint at `path` and saves the collection
+    fn set_field(&mut self, path: &str, field: &str, value: &str) -> Result<()> {
+        if self.current_endpoint(path).is_none() {
+            return Ok(());
+        }
 
-            self.parse_all();
+        if let Some(endpoint) = self.raw_data.pointer_mut(path) {
+            endpoint[field] = Value::String(String::from(value));
         }
+
+        self.parse_all()?;
+        self.update_file()
     }
 
-    pub fn insert_endpoint(&mut self, path: &str, name: &str) {
-        let new_path = self.build_new_path(path);
-        let closest_folder = self.find_closest_folder(path);
+    pub fn insert_endpoint(&mut self, path: &str, name: &str) -> Result<()> {
+        let new_path = self.build_new_path(path)?;
+        let closest_folder = self.find_closest_folder(path)?;
 
         let endpoint = NewEndpoint {
             r#type: String::from("endpoint"),
@@ -236,38 +537,74 @@ impl FolderTree {
             json_body: String::from("TODO"),
         };
 
+        let k = serde_json::to_value(&endpoint)?;
+
         let data_pointer = self
             .raw_data
             .pointer_mut(&closest_folder)
-            .unwrap()
-            .get_mut("items")
+            .and_then(|folder| folder.get_mut("items"))
             .and_then(Value::as_array_mut)
-            .unwrap();
-
-        let j = serde_json::to_string(&endpoint).unwrap();
-        let k: Value = serde_json::from_str(j.as_str()).unwrap();
+            .ok_or_else(|| Error::Schema(format!("{} has no items list", closest_folder)))?;
 
         data_pointer.push(k);
-        self.parse_all();
-        self.update_file();
+        self.parse_all()?;
+        self.update_file()
     }
 
-    fn update_file(&self) {
-        let s = self.path.as_str();
-        let p = Path::new(s);
-        let mut f = fs::File::create(p).unwrap();
+    fn update_file(&self) -> Result<()> {
+        let xd: String = serde_json::to_string_pretty(&self.raw_data)?;
+
+        fs::write(Path::new(&self.path), xd)?;
 
-        let xd: String = serde_json::to_string_pretty(&self.raw_data).unwrap();
+        Ok(())
+    }
 
-        use std::io::prelude::*;
+    pub fn parse_folder(&self, val: &Value, _folded: bool, indent: i32) -> Result<()> {
+        let arr = val
+            .get("items")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Schema(format!("{} has no items list", describe(val))))?;
 
-        f.write_all(xd.as_bytes()).unwrap();
+        self.parse(arr, indent)
     }
+}
+
+fn folder_item(val: &Value, folded: bool, indent: i32) -> Result<Item> {
+    let symbol = if folded { "▸" } else { "▾" };
+
+    Ok(Item {
+        rep: format!(
+            "{}{} 📁 {}",
+            construct_indent(indent),
+            symbol,
+            str_field(val, "name")?
+        ),
+        r#type: String::from("folder"),
+        obj_ref: str_field(val, "path")?.to_string(),
+        highlight: Vec::new(),
+    })
+}
 
-    pub fn parse_folder(&self, val: &Value, _folded: bool, indent: i32) {
-        let arr = val.get("items").and_then(Value::as_array).unwrap();
+// String field every item of its type must have
+fn str_field<'v>(val: &'v Value, field: &str) -> Result<&'v str> {
+    val.get(field).and_then(Value::as_str).ok_or_else(|| {
+        Error::Schema(format!(
+            "{} is missing the \"{}\" field",
+            describe(val),
+            field
+        ))
+    })
+}
 
-        self.parse(arr, indent);
+// How an item is named in errors, by its path or else its name
+fn describe(val: &Value) -> String {
+    match (
+        val.get("path").and_then(Value::as_str),
+        val.get("name").and_then(Value::as_str),
+    ) {
+        (Some(path), _) => format!("Item {}", path),
+        (None, Some(name)) => format!("Item {:?}", name),
+        (None, None) => String::from("An item"),
     }
 }
 
@@ -352,11 +689,135 @@ mod tests {
         FolderTree::new(file.path()).unwrap()
     }
 
+    #[test]
+    fn test_inherited_defaults() {
+        let mut ft = initialize();
+
+        ft.raw_data["base_url"] = serde_json::json!("http://localhost:3000");
+        ft.raw_data["variables"] = serde_json::json!({"user": "admin", "token": "t0"});
+        ft.raw_data["root"][0]["headers"] = serde_json::json!({"Accept": "text/plain"});
+        ft.raw_data["root"][0]["items"][2]["headers"] =
+            serde_json::json!({"accept": "application/json", "X-Token": "{{token}}"});
+        ft.raw_data["root"][0]["items"][2]["items"][0]["variables"] =
+            serde_json::json!({"token": "t1"});
+
+        let defaults = ft.inherited_defaults("/root/0/items/2/items/0");
+
+        assert_eq!(
+            defaults.base_url,
+            Some(String::from("http://localhost:3000"))
+        );
+        assert_eq!(
+            defaults.headers,
+            vec![
+                (String::from("accept"), String::from("application/json")),
+                (String::from("X-Token"), String::from("{{token}}")),
+            ]
+        );
+        assert_eq!(defaults.variables["user"], "admin");
+        assert_eq!(defaults.variables["token"], "t1");
+
+        let defaults = ft.inherited_defaults("/root/1");
+        assert_eq!(defaults.variables["token"], "t0");
+        assert!(defaults.headers.is_empty());
+    }
+
+    #[test]
+    fn test_header_order() {
+        let mut ft = initialize();
+
+        ft.raw_data["root"][0]["headers"] =
+            serde_json::json!({"X-B": "1", "Accept": "text/plain", "X-A": "2"});
+        ft.raw_data["root"][0]["items"][2]["headers"] =
+            serde_json::json!({"X-C": "3", "accept": "application/json"});
+
+        let names = ft
+            .inherited_defaults("/root/0/items/2/items/0")
+            .headers
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+
+        assert_eq!(names, vec!["X-B", "accept", "X-A", "X-C"]);
+    }
+
+    #[test]
+    fn test_inherited_auth_from_closest_folder() {
+        let mut ft = initialize();
+        let auth = serde_json::json!({
+            "type": "oauth2",
+            "grant_type": "client_credentials",
+            "token_url": "http://localhost:3000/token",
+            "client_id": "postui"
+        });
+
+        assert!(ft
+            .inherited_defaults("/root/0/items/2/items/0")
+            .auth
+            .is_none());
+
+        ft.raw_data["auth"] = auth.clone();
+        ft.raw_data["root"][0]["items"][2]["auth"] = auth;
+
+        let (scope, _) = ft
+            .inherited_defaults("/root/0/items/2/items/0")
+            .auth
+            .unwrap();
+        assert_eq!(scope, "/root/0/items/2");
+
+        let (scope, _) = ft.inherited_defaults("/root/0/items/0").auth.unwrap();
+        assert_eq!(scope, "/");
+    }
+
+    #[test]
+    fn test_active_environment() {
+        let mut ft = initialize();
+        ft.raw_data["variables"] = serde_json::json!({ "host": "localhost" });
+        ft.raw_data["settings"] = serde_json::json!({ "timeout_ms": 100 });
+        ft.raw_data["environments"] = serde_json::json!({
+            "staging": {
+                "variables": { "host": "staging.example.com" },
+                "settings": { "proxy": { "url": "socks5h://127.0.0.1:1080" } }
+            }
+        });
+
+        let defaults = ft.inherited_defaults("/root/0/items/0");
+        assert_eq!(defaults.variables["host"], "localhost");
+        assert!(defaults.settings.proxy.is_none());
+
+        ft.raw_data["active_environment"] = serde_json::json!("staging");
+
+        let defaults = ft.inherited_defaults("/root/0/items/0");
+        assert_eq!(defaults.environment.as_deref(), Some("staging"));
+        assert_eq!(defaults.variables["host"], "staging.example.com");
+        assert_eq!(defaults.settings.timeout_ms, Some(100));
+        assert_eq!(
+            defaults.settings.proxy.unwrap().url.as_deref(),
+            Some("socks5h://127.0.0.1:1080")
+        );
+    }
+
+    #[test]
+    fn test_find_endpoint() {
+        let tree = initialize();
+
+        assert_eq!(
+            tree.find_endpoint("Nested jeszcze").as_deref(),
+            Some("/root/0/items/2/items/0")
+        );
+        assert_eq!(tree.find_endpoint("/root/1").as_deref(), Some("/root/1"));
+        assert_eq!(tree.find_endpoint("Nested"), None);
+        assert_eq!(tree.find_endpoint("/root/0"), None);
+    }
+
     #[test]
     fn test_build_new_path_on_folder() {
         let ft = initialize();
 
-        assert_eq!(ft.build_new_path("/root/0"), String::from("/root/1"));
+        assert_eq!(
+            ft.build_new_path("/root/0").unwrap(),
+            String::from("/root/1")
+        );
     }
 
     #[test]
@@ -364,7 +825,7 @@ mod tests {
         let ft = initialize();
 
         assert_eq!(
-            ft.build_new_path("/root/0/items/1"),
+            ft.build_new_path("/root/0/items/1").unwrap(),
             String::from("/root/0/items/2")
         );
     }
@@ -374,7 +835,7 @@ mod tests {
         let ft = initialize();
 
         assert_eq!(
-            ft.find_closest_folder("/root/0/items/2"),
+            ft.find_closest_folder("/root/0/items/2").unwrap(),
             String::from("/root/0/items/2")
         );
     }
@@ -384,8 +845,90 @@ mod tests {
         let ft = initialize();
 
         assert_eq!(
-            ft.find_closest_folder("/root/0/items/2/items/0"),
+            ft.find_closest_folder("/root/0/items/2/items/0").unwrap(),
             String::from("/root/0/items/2")
         );
     }
+
+    #[test]
+    fn test_endpoints() {
+        let mut tree = initialize();
+        tree.fold_folder("/root/0").unwrap();
+
+        let endpoints = tree.endpoints();
+        assert_eq!(endpoints.len(), 4);
+        assert_eq!(endpoints[2].name, "Nested jeszcze");
+        assert_eq!(endpoints[2].folders, ["Pierwszy", "Nested"]);
+        assert!(endpoints[3].folders.is_empty());
+
+        tree.reveal(&endpoints[2].path).unwrap();
+        assert!(tree
+            .items
+            .borrow()
+            .iter()
+            .any(|item| item.obj_ref == endpoints[2].path));
+    }
+
+    #[test]
+    fn test_parse_filtered() {
+        let mut tree = initialize();
+        tree.fold_folder("/root/0/items/2").unwrap();
+
+        tree.parse_filtered("gnest").unwrap();
+        let reps = |tree: &FolderTree| {
+            tree.items
+                .borrow()
+                .iter()
+                .map(|item| item.obj_ref.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            reps(&tree),
+            ["/root/0", "/root/0/items/2", "/root/0/items/2/items/0"]
+        );
+
+        // "GET Nested jeszcze" under two levels of indent
+        let items = tree.items.borrow();
+        assert_eq!(items[2].highlight, [10, 14, 15, 16, 17]);
+        assert!(items[1].rep.contains('▾'));
+        drop(items);
+
+        tree.parse_filtered("3000/4").unwrap();
+        assert_eq!(reps(&tree), ["/root/1"]);
+        assert!(tree.items.borrow()[0].highlight.is_empty());
+
+        tree.parse_filtered("nothing like it").unwrap();
+        assert!(tree.items.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(
+            br#"{"root": [{"type": "endpoint", "name": "A", "method": "GET", "path": "/root/0"}]}"#,
+        )
+        .unwrap();
+
+        let mut ft = FolderTree::new(file.path()).unwrap();
+        ft.parse_all().unwrap();
+        assert_eq!(ft.items.borrow().len(), 1);
+
+        // A broken file keeps what was there before
+        std::fs::write(
+            file.path(),
+            r#"{"root": [{"type": "folder", "path": "/root/0"}]}"#,
+        )
+        .unwrap();
+
+        let e = ft.reload().unwrap_err();
+        assert_eq!(e.title(), "Collection error");
+        assert_eq!(e.to_string(), "Item /root/0 is missing the \"name\" field");
+        assert_eq!(ft.items.borrow().len(), 1);
+
+        std::fs::write(file.path(), "{").unwrap();
+        assert!(matches!(ft.reload(), Err(Error::Parse(_))));
+
+        // Top level endpoints have no folder to add next to
+        assert!(ft.insert_endpoint("/root/0", "B").is_err());
+    }
 }
@@ -0,0 +1,121 @@
+// Matching of typed patterns against names the way file pickers do it: the
+// pattern's characters have to show up in order, not next to each other
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 8;
+const WORD_START: i64 = 10;
+const GAP: i64 = 1;
+
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    // Higher is better
+    pub score: i64,
+
+    // Indices of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+// Case is ignored. An empty pattern matches everything equally.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<Match> {
+    let pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<char>>();
+    let chars = text.chars().collect::<Vec<char>>();
+    let lower = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<char>>();
+
+    if pattern.is_empty() {
+        return Some(Match {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    // Leftmost match first, to know where the pattern ends at the earliest
+    let mut end = 0;
+    let mut next = 0;
+    for (i, c) in lower.iter().enumerate() {
+        if *c == pattern[next] {
+            next += 1;
+            if next == pattern.len() {
+                end = i;
+                break;
+            }
+        }
+    }
+    if next < pattern.len() {
+        return None;
+    }
+
+    // Then backwards from there, which finds the tightest run of the pattern
+    // ending at that spot
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut remaining = pattern.len();
+    for i in (0..=end).rev() {
+        if lower[i] == pattern[remaining - 1] {
+            positions.push(i);
+            remaining -= 1;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    let score = positions
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| {
+            let mut score = MATCH;
+
+            if n > 0 {
+                let gap = (i - positions[n - 1] - 1) as i64;
+                score -= gap * GAP;
+                if gap == 0 {
+                    score += CONSECUTIVE;
+                }
+            }
+
+            if is_word_start(&chars, i) {
+                score += WORD_START;
+            }
+
+            score
+        })
+        .sum::<i64>()
+        - positions[0] as i64;
+
+    Some(Match { score, positions })
+}
+
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && chars[i].is_uppercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("abc", "xaxbxc").unwrap().positions, [1, 3, 5]);
+        assert_eq!(fuzzy_match("ABC", "abc").unwrap().positions, [0, 1, 2]);
+        assert!(fuzzy_match("cba", "abc").is_none());
+        assert_eq!(fuzzy_match("", "abc").unwrap().score, 0);
+
+        // The tighter run is picked over the first letters found
+        assert_eq!(fuzzy_match("ab", "a_xab").unwrap().positions, [3, 4]);
+
+        let score = |pattern, text| fuzzy_match(pattern, text).unwrap().score;
+        assert!(score("cu", "Change users") > score("cu", "Create account for user"));
+        assert!(score("send", "Send request") > score("send", "Payload sender"));
+        assert!(score("nf", "Nested folder") > score("nf", "Info"));
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::app::COLLECTION_PATH;
+use crate::components::{format::format_size, response_viewer::save_body};
+use crate::foldertree::FolderTree;
+use crate::http::{prepare_request, query_request, RequestContext};
+
+const USAGE: &str = "Usage: postui [--send <endpoint> [--output <file>]]";
+
+// Sends a single endpoint of the collection without starting the UI. The
+// endpoint is given by its name or its path in the collection, the body goes
+// to stdout unless --output names a file for it. Like saving from the UI, an
+// existing file is never overwritten.
+pub async fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut endpoint = None;
+    let mut output = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--send" => endpoint = args.next(),
+            "--output" | "-o" => output = args.next(),
+            _ => return Err(format!("Unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+
+    let endpoint = endpoint.ok_or(USAGE)?;
+
+    let tree = FolderTree::new(COLLECTION_PATH)?;
+    let path = tree
+        .find_endpoint(endpoint)
+        .ok_or_else(|| format!("No endpoint {} in the collection", endpoint))?;
+
+    let input = tree.current_endpoint(&path).unwrap_or_default();
+    let request = prepare_request(input, tree.inherited_defaults(&path));
+
+    let response = query_request(request, RequestContext::new(COLLECTION_PATH)).await?;
+
+    eprintln!("{} ({})", response.status, format_size(response.body.len()));
+
+    match output {
+        Some(file) => eprintln!("{}", save_body(Path::new(file), &response.body)?),
+        None => io::stdout().write_all(&response.body)?,
+    }
+
+    Ok(())
+}
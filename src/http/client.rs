@@ -0,0 +1,497 @@
+use reqwest::{redirect::Policy, Certificate, Identity, Proxy};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use url::Url;
+
+use crate::http::{connector::TimingConnector, CookieJar, Transport};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectPolicy {
+    Follow,
+    Limit(usize),
+    #[serde(rename = "none")]
+    Disabled,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+    Auto,
+    Http1,
+    Http2,
+}
+
+// PKCS#12 archive holding the client certificate and its key
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ClientCertificate {
+    pub path: String,
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ProxySettings {
+    // Proxy for every request, `http` and `https` take over for their own scheme.
+    // socks5:// and socks5h:// URLs work as well
+    pub url: Option<String>,
+    pub http: Option<String>,
+    pub https: Option<String>,
+
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    // Hosts reached directly, `*` matches all and `example.com` matches its subdomains too
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+
+    // HTTP_PROXY, HTTPS_PROXY and NO_PROXY are used when no proxy is set here
+    pub use_env: Option<bool>,
+}
+
+impl ProxySettings {
+    fn build(&self) -> Result<Option<Proxy>, Box<dyn Error>> {
+        let all = self.proxy_url(&self.url)?;
+        let http = self.proxy_url(&self.http)?.or_else(|| all.clone());
+        let https = self.proxy_url(&self.https)?.or(all);
+
+        if http.is_none() && https.is_none() {
+            return Ok(None);
+        }
+
+        let no_proxy = self.no_proxy.clone();
+
+        Ok(Some(Proxy::custom(move |url| {
+            if url
+                .host_str()
+                .is_some_and(|host| bypass_proxy(host, &no_proxy))
+            {
+                return None;
+            }
+
+            match url.scheme() {
+                "https" => https.clone(),
+                _ => http.clone(),
+            }
+        })))
+    }
+
+    // Credentials go into the proxy URL, reqwest picks them up from there for
+    // HTTP and SOCKS proxies alike
+    fn proxy_url(&self, url: &Option<String>) -> Result<Option<Url>, Box<dyn Error>> {
+        let mut url = match url {
+            Some(url) => Url::parse(url)?,
+            None => return Ok(None),
+        };
+
+        if let Some(username) = &self.username {
+            url.set_username(username)
+                .map_err(|_| format!("Can't set proxy username on {}", url))?;
+            url.set_password(self.password.as_deref())
+                .map_err(|_| format!("Can't set proxy password on {}", url))?;
+        }
+
+        Ok(Some(url))
+    }
+}
+
+fn bypass_proxy(host: &str, no_proxy: &[String]) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    no_proxy.iter().any(|rule| {
+        let rule = rule.trim().trim_start_matches('.');
+
+        rule == "*"
+            || host.eq_ignore_ascii_case(rule)
+            || host
+                .to_ascii_lowercase()
+                .ends_with(&format!(".{}", rule.to_ascii_lowercase()))
+    })
+}
+
+// Connection settings, everything left out falls back to the value set further
+// up (the collection) or to the reqwest defaults
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ClientSettings {
+    pub timeout_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+    pub redirect: Option<RedirectPolicy>,
+    pub http_version: Option<HttpVersion>,
+
+    // Encodings advertised in Accept-Encoding. Responses are decoded by postui
+    // itself, so their size on the wire is known too
+    pub gzip: Option<bool>,
+    pub brotli: Option<bool>,
+
+    pub verify_tls: Option<bool>,
+
+    // PEM file with extra root certificates
+    pub ca_bundle: Option<String>,
+
+    pub client_certificate: Option<ClientCertificate>,
+
+    pub proxy: Option<ProxySettings>,
+}
+
+impl ClientSettings {
+    // Values set in `overrides` win over ours
+    pub fn merge(&self, overrides: &ClientSettings) -> ClientSettings {
+        ClientSettings {
+            timeout_ms: overrides.timeout_ms.or(self.timeout_ms),
+            connect_timeout_ms: overrides.connect_timeout_ms.or(self.connect_timeout_ms),
+            redirect: overrides.redirect.clone().or_else(|| self.redirect.clone()),
+            http_version: overrides
+                .http_version
+                .clone()
+                .or_else(|| self.http_version.clone()),
+            gzip: overrides.gzip.or(self.gzip),
+            brotli: overrides.brotli.or(self.brotli),
+            verify_tls: overrides.verify_tls.or(self.verify_tls),
+            ca_bundle: overrides
+                .ca_bundle
+                .clone()
+                .or_else(|| self.ca_bundle.clone()),
+            client_certificate: overrides
+                .client_certificate
+                .clone()
+                .or_else(|| self.client_certificate.clone()),
+            proxy: overrides.proxy.clone().or_else(|| self.proxy.clone()),
+        }
+    }
+
+    pub fn accept_encoding(&self) -> Option<String> {
+        let mut encodings = Vec::new();
+
+        if self.gzip.unwrap_or(true) {
+            encodings.push("gzip");
+        }
+        if self.brotli.unwrap_or(true) {
+            encodings.push("br");
+        }
+
+        if encodings.is_empty() {
+            None
+        } else {
+            Some(encodings.join(", "))
+        }
+    }
+
+    // Whether requests may go through a proxy, set here or in the environment
+    fn uses_proxy(&self) -> bool {
+        let proxy = self.proxy.clone().unwrap_or_default();
+
+        if proxy.url.is_some() || proxy.http.is_some() || proxy.https.is_some() {
+            return true;
+        }
+
+        proxy.use_env.unwrap_or(true)
+            && ["http_proxy", "https_proxy", "all_proxy"]
+                .iter()
+                .any(|name| {
+                    std::env::var_os(name).is_some()
+                        || std::env::var_os(name.to_ascii_uppercase()).is_some()
+                })
+    }
+
+    fn tls(&self) -> Result<native_tls::TlsConnector, Box<dyn Error>> {
+        let mut builder = native_tls::TlsConnector::builder();
+
+        builder.danger_accept_invalid_certs(!self.verify_tls.unwrap_or(true));
+
+        match self.http_version {
+            Some(HttpVersion::Http1) => {}
+            Some(HttpVersion::Http2) => {
+                builder.request_alpns(&["h2"]);
+            }
+            Some(HttpVersion::Auto) | None => {
+                builder.request_alpns(&["h2", "http/1.1"]);
+            }
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let bundle = fs::read_to_string(path)?;
+
+            for pem in bundle.split_inclusive("-----END CERTIFICATE-----") {
+                if pem.contains("-----BEGIN CERTIFICATE-----") {
+                    builder
+                        .add_root_certificate(native_tls::Certificate::from_pem(pem.as_bytes())?);
+                }
+            }
+        }
+
+        if let Some(cert) = &self.client_certificate {
+            let der = fs::read(&cert.path)?;
+            let password = cert.password.as_deref().unwrap_or_default();
+
+            builder.identity(native_tls::Identity::from_pkcs12(&der, password)?);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    fn transport(&self) -> Result<Transport, Box<dyn Error>> {
+        let connect_timeout = self.connect_timeout_ms.map(Duration::from_millis);
+        let connector = TimingConnector::new(self.tls()?, connect_timeout);
+
+        let client = hyper::Client::builder()
+            .http2_only(self.http_version == Some(HttpVersion::Http2))
+            .build(connector);
+
+        Ok(Transport::new(
+            client,
+            self.redirect.clone(),
+            self.timeout_ms.map(Duration::from_millis),
+        ))
+    }
+
+    fn build(&self, cookies: Arc<CookieJar>) -> Result<reqwest::Client, Box<dyn Error>> {
+        let mut builder = reqwest::Client::builder().cookie_provider(cookies);
+
+        if let Some(ms) = self.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+
+        builder = builder.redirect(match self.redirect {
+            Some(RedirectPolicy::Limit(max)) => Policy::limited(max),
+            Some(RedirectPolicy::Disabled) => Policy::none(),
+            Some(RedirectPolicy::Follow) | None => Policy::default(),
+        });
+
+        builder = match self.http_version {
+            Some(HttpVersion::Http1) => builder.http1_only(),
+            Some(HttpVersion::Http2) => builder.http2_prior_knowledge(),
+            Some(HttpVersion::Auto) | None => builder,
+        };
+
+        // Bodies come back as they were sent, see `accept_encoding`
+        builder = builder
+            .gzip(false)
+            .brotli(false)
+            .danger_accept_invalid_certs(!self.verify_tls.unwrap_or(true));
+
+        if let Some(path) = &self.ca_bundle {
+            let bundle = fs::read_to_string(path)?;
+
+            // Certificate::from_pem only reads the first certificate of a bundle
+            for pem in bundle.split_inclusive("-----END CERTIFICATE-----") {
+                if pem.contains("-----BEGIN CERTIFICATE-----") {
+                    builder = builder.add_root_certificate(Certificate::from_pem(pem.as_bytes())?);
+                }
+            }
+        }
+
+        if let Some(cert) = &self.client_certificate {
+            let der = fs::read(&cert.path)?;
+            let password = cert.password.as_deref().unwrap_or_default();
+
+            builder = builder.identity(Identity::from_pkcs12_der(&der, password)?);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            builder = match proxy.build()? {
+                Some(proxy) => builder.proxy(proxy),
+                None if !proxy.use_env.unwrap_or(true) => builder.no_proxy(),
+                None => builder,
+            };
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+// Clients keep the connection pool alive between requests, so they're built
+// once for every distinct set of settings and then reused
+#[derive(Clone)]
+pub struct HttpClient {
+    cookies: Arc<CookieJar>,
+    clients: Arc<Mutex<HashMap<String, reqwest::Client>>>,
+    transports: Arc<Mutex<HashMap<String, Transport>>>,
+}
+
+impl HttpClient {
+    pub fn new(cookies: Arc<CookieJar>) -> Self {
+        Self {
+            cookies,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            transports: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // What the endpoints are sent with, so their connections can be timed.
+    // Nothing when a proxy may be used, reqwest takes care of those
+    pub fn transport(
+        &self,
+        settings: &ClientSettings,
+    ) -> Result<Option<Transport>, Box<dyn Error>> {
+        if settings.uses_proxy() {
+            return Ok(None);
+        }
+
+        let key = serde_json::to_string(settings)?;
+        let mut transports = self.transports.lock().unwrap();
+
+        if let Some(transport) = transports.get(&key) {
+            return Ok(Some(transport.clone()));
+        }
+
+        let transport = settings.transport()?;
+        transports.insert(key, transport.clone());
+
+        Ok(Some(transport))
+    }
+
+    pub fn get(&self, settings: &ClientSettings) -> Result<reqwest::Client, Box<dyn Error>> {
+        let key = serde_json::to_string(settings)?;
+        let mut clients = self.clients.lock().unwrap();
+
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let client = settings.build(self.cookies.clone())?;
+        clients.insert(key, client.clone());
+
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn test_merge() {
+        let global = ClientSettings {
+            timeout_ms: Some(1000),
+            redirect: Some(RedirectPolicy::Limit(3)),
+            verify_tls: Some(true),
+            ..ClientSettings::default()
+        };
+        let endpoint = ClientSettings {
+            timeout_ms: Some(50),
+            verify_tls: Some(false),
+            ..ClientSettings::default()
+        };
+
+        let merged = global.merge(&endpoint);
+
+        assert_eq!(merged.timeout_ms, Some(50));
+        assert_eq!(merged.redirect, Some(RedirectPolicy::Limit(3)));
+        assert_eq!(merged.verify_tls, Some(false));
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let settings: ClientSettings = serde_json::from_str(
+            r#"{"redirect": {"limit": 5}, "http_version": "http1", "gzip": false}"#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.redirect, Some(RedirectPolicy::Limit(5)));
+        assert_eq!(settings.http_version, Some(HttpVersion::Http1));
+        assert_eq!(settings.gzip, Some(false));
+
+        let settings: ClientSettings = serde_json::from_str(r#"{"redirect": "none"}"#).unwrap();
+        assert_eq!(settings.redirect, Some(RedirectPolicy::Disabled));
+    }
+
+    #[test]
+    fn test_bypass_proxy() {
+        let no_proxy = vec![String::from(".internal.net"), String::from("127.0.0.1")];
+
+        assert!(bypass_proxy("internal.net", &no_proxy));
+        assert!(bypass_proxy("api.Internal.net", &no_proxy));
+        assert!(bypass_proxy("127.0.0.1", &no_proxy));
+        assert!(!bypass_proxy("notinternal.net", &no_proxy));
+        assert!(bypass_proxy("anything", &[String::from("*")]));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+
+        let seen = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let dir = TempDir::new().unwrap();
+        let clients = HttpClient::new(Arc::new(CookieJar::load(dir.path().join("c.json"))));
+        let settings = ClientSettings {
+            proxy: Some(ProxySettings {
+                url: Some(proxy),
+                username: Some(String::from("user")),
+                password: Some(String::from("pass")),
+                ..ProxySettings::default()
+            }),
+            ..ClientSettings::default()
+        };
+
+        let response = clients
+            .get(&settings)
+            .unwrap()
+            .get("http://example.invalid/users")
+            .send()
+            .await
+            .unwrap();
+        let seen = seen.await.unwrap();
+
+        assert_eq!(response.status(), 204);
+        assert!(seen.starts_with("GET http://example.invalid/users HTTP/1.1"));
+        assert!(seen
+            .to_ascii_lowercase()
+            .contains("proxy-authorization: basic dxnlcjpwyxnz"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_can_be_disabled() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let dir = TempDir::new().unwrap();
+        let clients = HttpClient::new(Arc::new(CookieJar::load(dir.path().join("c.json"))));
+        let settings = ClientSettings {
+            redirect: Some(RedirectPolicy::Disabled),
+            ..ClientSettings::default()
+        };
+
+        let response = clients
+            .get(&settings)
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 302);
+    }
+}
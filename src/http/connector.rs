@@ -0,0 +1,205 @@
+use hyper::{
+    client::connect::{Connected, Connection},
+    service::Service,
+    Uri,
+};
+use std::{
+    error::Error,
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{lookup_host, TcpStream},
+};
+use tokio_native_tls::{TlsConnector, TlsStream};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// How long opening a connection took. Every response sent over the connection
+// gets a copy, only the first one to claim it counts these phases as its own
+#[derive(Clone, Debug)]
+pub struct ConnectionTimings {
+    pub dns: Duration,
+    pub connect: Duration,
+    pub tls: Option<Duration>,
+
+    claimed: Arc<AtomicBool>,
+}
+
+impl ConnectionTimings {
+    // Nothing when an earlier response already used the connection
+    pub fn claim(&self) -> Option<&Self> {
+        if self.claimed.swap(true, Ordering::SeqCst) {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+// Opens the connections of the hyper client, resolving the host, connecting
+// and shaking hands one step at a time so each of them can be timed
+#[derive(Clone)]
+pub struct TimingConnector {
+    tls: TlsConnector,
+
+    // Covers the whole connection, DNS and TLS included
+    timeout: Option<Duration>,
+}
+
+impl TimingConnector {
+    pub fn new(tls: native_tls::TlsConnector, timeout: Option<Duration>) -> Self {
+        Self {
+            tls: TlsConnector::from(tls),
+            timeout,
+        }
+    }
+
+    async fn open(self, uri: Uri) -> Result<TimedStream, BoxError> {
+        let https = uri.scheme_str() == Some("https");
+        let host = uri
+            .host()
+            .ok_or_else(|| format!("No host in {}", uri))?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+        let started = Instant::now();
+        let addrs = lookup_host((host, port)).await?.collect::<Vec<_>>();
+        let dns = started.elapsed();
+
+        let started = Instant::now();
+        let tcp = connect(&addrs).await?;
+        let connect = started.elapsed();
+        tcp.set_nodelay(true)?;
+
+        let (stream, tls) = if https {
+            let started = Instant::now();
+            let stream = self.tls.connect(host, tcp).await?;
+            (Stream::Tls(Box::new(stream)), Some(started.elapsed()))
+        } else {
+            (Stream::Tcp(tcp), None)
+        };
+
+        Ok(TimedStream {
+            stream,
+            timings: ConnectionTimings {
+                dns,
+                connect,
+                tls,
+                claimed: Arc::default(),
+            },
+        })
+    }
+}
+
+impl Service<Uri> for TimingConnector {
+    type Response = TimedStream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<TimedStream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = self.clone();
+
+        Box::pin(async move {
+            match connector.timeout {
+                Some(limit) => tokio::time::timeout(limit, connector.open(uri))
+                    .await
+                    .map_err(|_| "Timed out while connecting")?,
+                None => connector.open(uri).await,
+            }
+        })
+    }
+}
+
+// The addresses are tried in the order the resolver gave them
+async fn connect(addrs: &[SocketAddr]) -> io::Result<TcpStream> {
+    let mut last_error = None;
+
+    for addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No address for the host")))
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+pub struct TimedStream {
+    stream: Stream,
+    timings: ConnectionTimings,
+}
+
+impl Connection for TimedStream {
+    fn connected(&self) -> Connected {
+        let connected = Connected::new().extra(self.timings.clone());
+
+        match &self.stream {
+            Stream::Tls(stream) => match stream.get_ref().negotiated_alpn() {
+                Ok(Some(protocol)) if protocol == b"h2" => connected.negotiated_h2(),
+                _ => connected,
+            },
+            Stream::Tcp(_) => connected,
+        }
+    }
+}
+
+impl AsyncRead for TimedStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Stream::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Stream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for TimedStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().stream {
+            Stream::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Stream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Stream::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Stream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Stream::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Stream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+
+// Media type without its parameters, lowercased: "Text/HTML; charset=utf-8"
+// becomes "text/html"
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+// Value of the charset parameter, if there is one
+pub fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+
+        match name.trim().eq_ignore_ascii_case("charset") {
+            true => Some(value.trim().trim_matches('"')),
+            false => None,
+        }
+    })
+}
+
+// Body as text in the charset from its content type, UTF-8 when there's none
+// or it's unknown. A byte order mark wins over both. Valid UTF-8 is borrowed
+// from the body rather than copied.
+pub fn decode_text<'b>(content_type: Option<&str>, body: &'b [u8]) -> Cow<'b, str> {
+    let encoding = content_type
+        .and_then(charset)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+
+    let (text, _, _) = encoding.decode(body);
+
+    text
+}
+
+// Whether a body is better shown as a hex dump than as text. Known text types
+// never are, known binary ones always are and everything else is sniffed.
+pub fn is_binary(content_type: Option<&str>, body: &[u8]) -> bool {
+    let media_type = content_type.map(media_type).unwrap_or_default();
+
+    // Anything with a charset is text
+    let text = content_type.and_then(charset).is_some()
+        || media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-www-form-urlencoded"
+                | "application/graphql"
+                | "image/svg+xml"
+        );
+
+    if text {
+        return false;
+    }
+
+    let binary = ["image/", "audio/", "video/", "font/"]
+        .iter()
+        .any(|prefix| media_type.starts_with(prefix))
+        || matches!(
+            media_type.as_str(),
+            "application/octet-stream"
+                | "application/pdf"
+                | "application/zip"
+                | "application/gzip"
+                | "application/protobuf"
+                | "application/x-protobuf"
+                | "application/vnd.google.protobuf"
+                | "application/grpc"
+                | "application/wasm"
+        );
+
+    if binary {
+        return true;
+    }
+
+    // A NUL byte or broken UTF-8 close to the start gives binaries away
+    let head = &body[..body.len().min(1024)];
+
+    match std::str::from_utf8(head) {
+        Ok(text) => text.contains('\0'),
+        // The head may have cut a character in half
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+// File extension that fits a content type, for naming saved bodies
+pub fn extension(content_type: Option<&str>) -> &'static str {
+    let media_type = content_type.map(media_type).unwrap_or_default();
+
+    match media_type.as_str() {
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "text/html" => "html",
+        "text/csv" => "csv",
+        "application/javascript" | "text/javascript" => "js",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "application/protobuf" | "application/x-protobuf" => "pb",
+        t if t.ends_with("+json") => "json",
+        t if t.ends_with("+xml") => "xml",
+        t if t.starts_with("text/") => "txt",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(Some("application/json; charset=utf-8"), b"{}"));
+        assert!(!is_binary(Some("application/problem+json"), b"{}"));
+        assert!(is_binary(Some("image/png"), b"\x89PNG"));
+        assert!(is_binary(Some("Application/PDF"), b"%PDF-1.4"));
+
+        // Sniffed
+        assert!(!is_binary(None, "zażółć".as_bytes()));
+        assert!(!is_binary(None, &"ż".as_bytes()[..1]));
+        assert!(is_binary(None, b"PK\x03\x04\x00\x00"));
+        assert!(is_binary(Some("application/x-whatever"), b"\xff\xfe\xfd"));
+
+        assert_eq!(extension(Some("image/jpeg")), "jpg");
+        assert_eq!(extension(Some("text/plain; charset=utf-8")), "txt");
+        assert_eq!(extension(None), "bin");
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(
+            charset("text/html; Charset=\"ISO-8859-2\""),
+            Some("ISO-8859-2")
+        );
+        assert_eq!(charset("text/html"), None);
+
+        // "żółw" in ISO-8859-2 and windows-1250
+        assert_eq!(
+            decode_text(Some("text/plain; charset=iso-8859-2"), b"\xbf\xf3\xb3w"),
+            "żółw"
+        );
+        assert_eq!(
+            decode_text(Some("text/plain;charset=windows-1250"), b"\xbf\xf3\xb3w"),
+            "żółw"
+        );
+        assert_eq!(decode_text(None, "żółw".as_bytes()), "żółw");
+        assert_eq!(decode_text(Some("text/plain; charset=bogus"), b"ok"), "ok");
+    }
+}
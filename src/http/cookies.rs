@@ -0,0 +1,224 @@
+use bytes::Bytes;
+use chrono::{TimeZone, Utc};
+use cookie_store::{Cookie, CookieExpiration, CookieStore as Store};
+use reqwest::{cookie::CookieStore, header::HeaderValue};
+use std::{
+    fs,
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+use url::Url;
+
+// Cookies of a collection, kept in a file next to its config
+pub struct CookieJar {
+    store: RwLock<Store>,
+    path: PathBuf,
+}
+
+// A single cookie as listed in the inspector
+#[derive(Clone, Debug, PartialEq)]
+pub struct CookieEntry {
+    pub domain: String,
+    pub path: String,
+    pub name: String,
+    pub value: String,
+    pub expires: Option<String>,
+}
+
+impl CookieJar {
+    pub fn load<P: AsRef<Path>>(collection: P) -> Self {
+        let path = collection.as_ref().with_extension("cookies.json");
+
+        let store = match fs::File::open(&path) {
+            Ok(file) => Store::load_json(BufReader::new(file)).unwrap_or_else(|e| {
+                log::error!("Failed to load cookies from {}: {}", path.display(), e);
+                Store::default()
+            }),
+            Err(_) => Store::default(),
+        };
+
+        Self {
+            store: RwLock::new(store),
+            path,
+        }
+    }
+
+    // Session cookies are saved too, postui sessions are usually short but
+    // logging in again each time is annoying
+    pub fn save(&self) {
+        let store = self.store.read().unwrap();
+
+        let result = fs::File::create(&self.path).and_then(|mut file| {
+            for cookie in store.iter_unexpired() {
+                let line = serde_json::to_string(cookie)?;
+                writeln!(file, "{}", line)?;
+            }
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            log::error!("Failed to save cookies to {}: {}", self.path.display(), e);
+        }
+    }
+
+    // All the cookies that haven't expired yet, sorted by domain
+    pub fn entries(&self) -> Vec<CookieEntry> {
+        let store = self.store.read().unwrap();
+
+        let mut entries = store
+            .iter_unexpired()
+            .map(|cookie| CookieEntry {
+                domain: cookie
+                    .domain
+                    .as_cow()
+                    .map_or_else(String::new, |d| d.into_owned()),
+                path: String::from(cookie.path.as_ref()),
+                name: String::from(cookie.name()),
+                value: String::from(cookie.value()),
+                expires: match cookie.expires {
+                    CookieExpiration::AtUtc(at) => Some(
+                        Utc.timestamp(at.unix_timestamp(), 0)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string(),
+                    ),
+                    CookieExpiration::SessionEnd => None,
+                },
+            })
+            .collect::<Vec<CookieEntry>>();
+
+        entries.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+
+        entries
+    }
+
+    pub fn remove(&self, entry: &CookieEntry) {
+        self.store
+            .write()
+            .unwrap()
+            .remove(&entry.domain, &entry.path, &entry.name);
+
+        self.save();
+    }
+
+    pub fn set_value(&self, entry: &CookieEntry, value: &str) {
+        {
+            let mut store = self.store.write().unwrap();
+
+            let old = match store.remove(&entry.domain, &entry.path, &entry.name) {
+                Some(old) => old,
+                None => return,
+            };
+
+            let mut raw = (*old).clone();
+            raw.set_value(String::from(value));
+
+            // The URL only has to match the cookie so it's stored under the same domain and path
+            let url = Url::parse(&format!("https://{}{}", entry.domain, entry.path));
+            let cookie = url
+                .ok()
+                .and_then(|url| Some((Cookie::try_from_raw_cookie(&raw, &url).ok()?, url)));
+
+            match cookie {
+                Some((cookie, url)) => {
+                    if let Err(e) = store.insert(cookie.into_owned(), &url) {
+                        log::error!("Failed to update cookie {}: {}", entry.name, e);
+                    }
+                }
+                None => log::error!("Failed to update cookie {}", entry.name),
+            }
+        }
+
+        self.save();
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| Cookie::parse(value, url).ok())
+            .map(|cookie| (*cookie).clone().into_owned())
+            .collect::<Vec<_>>();
+
+        if cookies.is_empty() {
+            return;
+        }
+
+        self.store
+            .write()
+            .unwrap()
+            .store_response_cookies(cookies.into_iter(), url);
+
+        self.save();
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        if header.is_empty() {
+            return None;
+        }
+
+        HeaderValue::from_maybe_shared(Bytes::from(header)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn set_cookie(jar: &CookieJar, header: &'static str, url: &Url) {
+        let value = HeaderValue::from_static(header);
+        jar.set_cookies(&mut std::iter::once(&value), url);
+    }
+
+    #[test]
+    fn test_cookies_are_sent_back_and_persisted() {
+        let dir = TempDir::new().unwrap();
+        let collection = dir.path().join("config.json");
+        let url = Url::parse("http://localhost:3000/login").unwrap();
+
+        let jar = CookieJar::load(&collection);
+        set_cookie(&jar, "session=abc; Path=/", &url);
+
+        let other = Url::parse("http://localhost:3000/users").unwrap();
+        assert_eq!(jar.cookies(&other).unwrap(), "session=abc");
+
+        let reloaded = CookieJar::load(&collection);
+        assert_eq!(reloaded.entries().len(), 1);
+        assert_eq!(reloaded.entries()[0].value, "abc");
+        assert_eq!(reloaded.entries()[0].domain, "localhost");
+    }
+
+    #[test]
+    fn test_edit_and_remove() {
+        let dir = TempDir::new().unwrap();
+        let jar = CookieJar::load(dir.path().join("config.json"));
+        let url = Url::parse("http://localhost:3000/").unwrap();
+
+        set_cookie(&jar, "a=1; Path=/", &url);
+        set_cookie(&jar, "b=2; Path=/", &url);
+
+        let entries = jar.entries();
+        jar.set_value(&entries[0], "changed");
+
+        let values = jar
+            .entries()
+            .into_iter()
+            .map(|e| e.value)
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["changed", "2"]);
+
+        jar.remove(&entries[1]);
+        assert_eq!(jar.cookies(&url).unwrap(), "a=changed");
+    }
+}
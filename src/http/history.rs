@@ -0,0 +1,191 @@
+use chrono::{DateTime, Local};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::http::{Metrics, Response};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Pending,
+    Status(u16),
+    Failed(String),
+    Cancelled,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Pending => write!(f, "Pending"),
+            Outcome::Status(status) => write!(f, "{}", status),
+            Outcome::Failed(e) => write!(f, "Error: {}", e),
+            Outcome::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    // Path of the endpoint in the collection
+    pub endpoint: String,
+
+    pub method: String,
+    pub url: String,
+    pub sent_at: DateTime<Local>,
+    pub started: Instant,
+    pub elapsed: Option<Duration>,
+    pub outcome: Outcome,
+
+    // Only there for requests that got a response
+    pub metrics: Option<Metrics>,
+
+    // Only kept for the newest response of each endpoint, so downloading
+    // something large again and again doesn't keep every copy of it
+    pub response: Option<Arc<Response>>,
+}
+
+// Every request sent during this session, oldest first
+#[derive(Clone, Default)]
+pub struct History {
+    entries: Arc<Mutex<Vec<HistoryEntry>>>,
+}
+
+impl History {
+    pub fn start(&self, endpoint: &str, method: &str, url: &str) -> u64 {
+        let mut entries = self.entries.lock().unwrap();
+        let id = entries.len() as u64;
+
+        entries.push(HistoryEntry {
+            endpoint: String::from(endpoint),
+            method: String::from(method),
+            url: String::from(url),
+            sent_at: Local::now(),
+            started: Instant::now(),
+            elapsed: None,
+            outcome: Outcome::Pending,
+            metrics: None,
+            response: None,
+        });
+
+        id
+    }
+
+    // Only the first outcome counts, so a request finishing right as it's
+    // cancelled isn't recorded twice
+    pub fn finish(&self, id: u64, outcome: Outcome, response: Option<Response>) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+
+        let endpoint = match entries.get_mut(id as usize) {
+            Some(entry) if entry.outcome == Outcome::Pending => {
+                entry.elapsed = Some(entry.started.elapsed());
+                entry.outcome = outcome;
+                entry.metrics = response.as_ref().map(|r| r.metrics.clone());
+                entry.response = response.map(Arc::new);
+                entry.endpoint.clone()
+            }
+            _ => return false,
+        };
+
+        // A request sent later that already finished has the newer body
+        let (older, newer) = entries.split_at_mut(id as usize);
+        let (entry, newer) = newer.split_first_mut().unwrap();
+
+        if newer
+            .iter()
+            .any(|e| e.endpoint == endpoint && e.response.is_some())
+        {
+            entry.response = None;
+        } else if entry.response.is_some() {
+            for e in older.iter_mut().filter(|e| e.endpoint == endpoint) {
+                e.response = None;
+            }
+        }
+
+        true
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn last_for(&self, endpoint: &str) -> Option<HistoryEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|e| e.endpoint == endpoint)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_outcome_wins() {
+        let history = History::default();
+
+        let first = history.start("/root/0", "GET", "http://localhost/a");
+        let second = history.start("/root/1", "POST", "http://localhost/b");
+
+        assert!(history.finish(first, Outcome::Cancelled, None));
+        assert!(!history.finish(first, Outcome::Status(200), None));
+        assert!(history.finish(second, Outcome::Status(201), None));
+
+        let entries = history.entries();
+        assert_eq!(entries[0].outcome, Outcome::Cancelled);
+        assert!(entries[0].elapsed.is_some());
+        assert_eq!(
+            history.last_for("/root/1").unwrap().outcome,
+            Outcome::Status(201)
+        );
+    }
+
+    #[test]
+    fn test_only_newest_body_is_kept() {
+        let history = History::default();
+        let response = |size| Response {
+            status: reqwest::StatusCode::OK,
+            headers: Vec::new(),
+            body: bytes::Bytes::from(vec![0; size]),
+            metrics: Metrics::default(),
+        };
+
+        let first = history.start("/root/0", "GET", "http://localhost/a");
+        let other = history.start("/root/1", "GET", "http://localhost/b");
+        let second = history.start("/root/0", "GET", "http://localhost/a");
+
+        history.finish(first, Outcome::Status(200), Some(response(10)));
+        history.finish(other, Outcome::Status(200), Some(response(20)));
+        history.finish(second, Outcome::Status(200), Some(response(30)));
+
+        let entries = history.entries();
+        assert!(entries[0].response.is_none());
+        assert!(entries[0].metrics.is_some());
+        assert_eq!(entries[1].response.as_ref().unwrap().body.len(), 20);
+        assert_eq!(entries[2].response.as_ref().unwrap().body.len(), 30);
+
+        // One sent earlier but answered last doesn't replace the newer body
+        let slow = history.start("/root/1", "GET", "http://localhost/b");
+        let fast = history.start("/root/1", "GET", "http://localhost/b");
+        history.finish(fast, Outcome::Status(200), Some(response(40)));
+        history.finish(slow, Outcome::Status(200), Some(response(50)));
+
+        let entries = history.entries();
+        assert!(entries[slow as usize].response.is_none());
+        assert_eq!(
+            history
+                .last_for("/root/1")
+                .unwrap()
+                .response
+                .unwrap()
+                .body
+                .len(),
+            40
+        );
+    }
+}
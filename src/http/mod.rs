@@ -0,0 +1,354 @@
+pub mod client;
+pub mod connector;
+pub mod content;
+pub mod cookies;
+pub mod history;
+pub mod oauth;
+pub mod signing;
+pub mod timing;
+pub mod transport;
+pub mod variables;
+
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, ACCEPT, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{to_string, Map, Value};
+use std::{path::Path, sync::Arc};
+
+use crate::error::Error;
+use crate::foldertree::Defaults;
+use variables::{resolve_url, substitute};
+
+pub use client::{ClientSettings, HttpClient};
+pub use cookies::{CookieEntry, CookieJar};
+pub use history::{History, HistoryEntry, Outcome};
+pub use oauth::{OAuth2Config, TokenCache};
+pub use signing::{CanonicalRequestLog, HmacConfig, SigV4Config};
+pub use timing::{Metrics, Sizes, Timings};
+pub use transport::Transport;
+
+// Authentication configured on a folder or on the whole collection
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    Oauth2(OAuth2Config),
+    AwsSigv4(SigV4Config),
+    Hmac(HmacConfig),
+}
+
+impl Auth {
+    pub fn name(&self) -> &str {
+        match self {
+            Auth::Oauth2(_) => "oauth2",
+            Auth::AwsSigv4(_) => "aws_sigv4",
+            Auth::Hmac(_) => "hmac",
+        }
+    }
+}
+
+// State shared by all the requests sent from the app
+#[derive(Clone)]
+pub struct RequestContext {
+    pub client: HttpClient,
+    pub tokens: TokenCache,
+    pub canonical_requests: CanonicalRequestLog,
+    pub cookies: Arc<CookieJar>,
+    pub history: History,
+}
+
+impl RequestContext {
+    pub fn new<P: AsRef<Path>>(collection: P) -> Self {
+        let cookies = Arc::new(CookieJar::load(collection));
+
+        Self {
+            client: HttpClient::new(cookies.clone()),
+            tokens: TokenCache::default(),
+            canonical_requests: CanonicalRequestLog::default(),
+            cookies,
+            history: History::default(),
+        }
+    }
+}
+
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub json_body: String,
+
+    // Connection settings of the collection merged with the endpoint's own
+    pub settings: ClientSettings,
+
+    // Auth inherited by the endpoint
+    pub auth: Option<Auth>,
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+
+    // Decoded body, shared with the views made from it
+    pub body: Bytes,
+
+    pub metrics: Metrics,
+}
+
+impl Response {
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_binary(&self) -> bool {
+        content::is_binary(self.content_type(), &self.body)
+    }
+}
+
+pub fn prepare_request(input: Map<String, Value>, defaults: Defaults) -> Request {
+    let vars = &defaults.variables;
+    let empty = || String::from("");
+    let get_val = |val: &Value| to_string(val).unwrap();
+
+    let url = input
+        .get("url")
+        .map_or_else(empty, get_val)
+        .replace("\"", "");
+    let method = input
+        .get("method")
+        .map_or_else(empty, get_val)
+        .replace("\"", "");
+
+    let base_url = defaults.base_url.as_ref().map(|b| substitute(b, vars));
+    let url = resolve_url(&substitute(&url, vars), base_url.as_deref());
+
+    let headers = defaults
+        .headers
+        .iter()
+        .map(|(name, value)| (name.clone(), substitute(value, vars)))
+        .collect();
+
+    let json_body = input
+        .get("json_body")
+        .and_then(Value::as_str)
+        .map_or_else(empty, |body| substitute(body, vars));
+
+    Request {
+        url,
+        method,
+        headers,
+        json_body,
+        settings: defaults.settings,
+        auth: defaults.auth.map(|(_, auth)| auth),
+    }
+}
+
+pub async fn query_request(input: Request, context: RequestContext) -> Result<Response, Error> {
+    let client = context.client.get(&input.settings)?;
+
+    // Anything that's a valid token works, so custom verbs like PROPFIND go through as well
+    let method = Method::from_bytes(input.method.trim().to_ascii_uppercase().as_bytes())
+        .map_err(|_| Error::Http(format!("Invalid method {:?}", input.method)))?;
+
+    let mut builder = client.request(method.clone(), input.url);
+
+    for (name, value) in input.headers.iter() {
+        builder = builder.header(name, value);
+    }
+
+    let headers = &input.headers;
+    let has_header = |header: &str| {
+        headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(header))
+    };
+
+    // reqwest's default, kept for the requests it no longer sends
+    if !has_header(ACCEPT.as_str()) {
+        builder = builder.header(ACCEPT, "*/*");
+    }
+
+    if let (false, Some(encodings)) = (
+        has_header(ACCEPT_ENCODING.as_str()),
+        input.settings.accept_encoding(),
+    ) {
+        builder = builder.header(ACCEPT_ENCODING, encodings);
+    }
+
+    let has_body = !matches!(method, Method::GET | Method::HEAD | Method::TRACE);
+
+    if has_body && !input.json_body.is_empty() {
+        if !has_header(CONTENT_TYPE.as_str()) {
+            builder = builder.header(CONTENT_TYPE, "application/json");
+        }
+
+        builder = builder.body(input.json_body);
+    }
+
+    if let Some(Auth::Oauth2(config)) = &input.auth {
+        let header = context.tokens.authorization(config, &client).await?;
+        builder = builder.header(AUTHORIZATION, header);
+    }
+
+    // Signatures have to cover the request exactly as it will be sent
+    let mut request = builder.build()?;
+
+    if let Some(auth) = &input.auth {
+        if let Some(canonical) = signing::sign_request(&mut request, auth)? {
+            log::debug!("Signed request:\n{}", canonical);
+            context.canonical_requests.set(canonical);
+        }
+    }
+
+    let mut sizes = Sizes {
+        request_headers: format!("{} {} HTTP/1.1\r\n", request.method(), request.url().path())
+            .len()
+            + headers_size(request.headers()),
+        request_body: request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map_or(0, |b| b.len()),
+        ..Sizes::default()
+    };
+
+    let transport = context.client.transport(&input.settings)?;
+    let exchange = match transport {
+        Some(transport) => transport.send(request, &context.cookies).await?,
+        None => transport::send_with_reqwest(&client, request).await?,
+    };
+
+    let headers = exchange
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (String::from(name.as_str()), value)
+        })
+        .collect();
+
+    sizes.response_headers =
+        format!("HTTP/1.1 {}\r\n", exchange.status).len() + headers_size(&exchange.headers);
+
+    let encoding = exchange
+        .headers
+        .get(CONTENT_ENCODING)
+        .and_then(|e| e.to_str().ok());
+
+    sizes.response_body = exchange.body.len();
+    let body = match encoding {
+        Some(_) => Bytes::from(timing::decode_body(encoding, exchange.body.to_vec())?),
+        None => exchange.body,
+    };
+    sizes.decoded_body = body.len();
+
+    Ok(Response {
+        status: exchange.status,
+        headers,
+        body,
+        metrics: Metrics {
+            timings: exchange.timings,
+            sizes,
+        },
+    })
+}
+
+// Size of the headers as written on the wire, including the empty line after them
+fn headers_size(headers: &HeaderMap) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum::<usize>()
+        + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[tokio::test]
+    async fn test_custom_methods() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/files", listener.local_addr().unwrap());
+
+        let seen = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 207 Multi-Status\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let dir = TempDir::new().unwrap();
+        let context = RequestContext::new(dir.path().join("config.json"));
+
+        let mut endpoint = Map::new();
+        endpoint.insert(String::from("method"), Value::from("propfind"));
+        endpoint.insert(String::from("url"), Value::from(url));
+
+        let request = prepare_request(endpoint, Defaults::default());
+        let response = query_request(request, context).await.unwrap();
+
+        assert_eq!(response.status, 207);
+
+        assert!(seen.await.unwrap().starts_with("PROPFIND /files HTTP/1.1"));
+
+        let mut endpoint = Map::new();
+        endpoint.insert(String::from("method"), Value::from("NOT A VERB"));
+        let request = prepare_request(endpoint, Defaults::default());
+        let context = RequestContext::new(dir.path().join("config.json"));
+
+        assert!(query_request(request, context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gzip_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        // "hello hello hello", gzipped
+        let body: [u8; 28] = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00, 0x80, 0x88, 0xf9, 0xe5, 0x11, 0x00, 0x00, 0x00,
+        ];
+
+        let seen = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            let head = "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 28\r\n\r\n";
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+
+            String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase()
+        });
+
+        let dir = TempDir::new().unwrap();
+        let context = RequestContext::new(dir.path().join("config.json"));
+
+        let mut endpoint = Map::new();
+        endpoint.insert(String::from("method"), Value::from("GET"));
+        endpoint.insert(String::from("url"), Value::from(url));
+
+        let request = prepare_request(endpoint, Defaults::default());
+        let response = query_request(request, context).await.unwrap();
+
+        assert_eq!(&response.body[..], b"hello hello hello");
+        assert_eq!(response.metrics.sizes.response_body, 28);
+        assert_eq!(response.metrics.sizes.decoded_body, 17);
+        assert!(seen.await.unwrap().contains("accept-encoding: gzip, br"));
+    }
+}
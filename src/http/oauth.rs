@@ -0,0 +1,584 @@
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use url::Url;
+
+// Tokens are refreshed this long before they actually expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+// How long we wait for the browser to come back to the loopback listener
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantType {
+    ClientCredentials,
+    Password,
+    RefreshToken,
+    AuthorizationCode,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OAuth2Config {
+    pub grant_type: GrantType,
+    pub token_url: String,
+
+    // Only used by the authorization code grant
+    pub auth_url: Option<String>,
+
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+
+    // Only used by the password grant
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    // Only used by the refresh token grant
+    pub refresh_token: Option<String>,
+
+    // Port of the loopback redirect listener, 0 picks any free port
+    #[serde(default)]
+    pub redirect_port: u16,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: Option<String>,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Clone)]
+struct Token {
+    access_token: String,
+    token_type: String,
+    expires_at: Option<Instant>,
+    refresh_token: Option<String>,
+}
+
+impl Token {
+    fn is_fresh(&self) -> bool {
+        match self.expires_at {
+            Some(at) => Instant::now() + EXPIRY_MARGIN < at,
+            None => true,
+        }
+    }
+
+    fn header(&self) -> String {
+        format!("{} {}", self.token_type, self.access_token)
+    }
+}
+
+impl From<TokenResponse> for Token {
+    fn from(response: TokenResponse) -> Self {
+        // Some servers answer with lowercase "bearer", which not every API accepts
+        let token_type = match response.token_type {
+            Some(t) if !t.eq_ignore_ascii_case("bearer") => t,
+            _ => String::from("Bearer"),
+        };
+
+        Token {
+            access_token: response.access_token,
+            token_type,
+            expires_at: response
+                .expires_in
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+            refresh_token: response.refresh_token,
+        }
+    }
+}
+
+// Access tokens shared by every request, keyed by the auth settings they were
+// fetched with. Folders with the same settings share a token, and editing
+// them gets a new one. Each key has its own lock, so a slow exchange (like
+// waiting for the browser) only holds up the requests that need the same token
+#[derive(Clone, Default)]
+pub struct TokenCache {
+    tokens: Arc<Mutex<HashMap<String, TokenSlot>>>,
+}
+
+type TokenSlot = Arc<tokio::sync::Mutex<Option<Token>>>;
+
+impl TokenCache {
+    // Returns a value for the `Authorization` header, fetching a new token or
+    // refreshing the cached one when it is about to expire
+    pub async fn authorization(
+        &self,
+        config: &OAuth2Config,
+        client: &reqwest::Client,
+    ) -> Result<String, Box<dyn Error>> {
+        let slot = self.slot(config);
+
+        // Holding the key's lock for the whole exchange makes concurrent
+        // requests wait for a single token instead of each fetching their own
+        let mut cached = slot.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if token.is_fresh() {
+                return Ok(token.header());
+            }
+        }
+
+        let mut refreshed = None;
+
+        if let Some(refresh_token) = cached.as_ref().and_then(|t| t.refresh_token.clone()) {
+            match refresh(client, config, &refresh_token).await {
+                Ok(token) => refreshed = Some(token),
+                Err(e) => log::warn!("Refreshing token failed, requesting a new one: {}", e),
+            }
+        }
+
+        let token = match refreshed {
+            Some(token) => token,
+            None => fetch_token(client, config).await?,
+        };
+
+        let header = token.header();
+        *cached = Some(token);
+
+        Ok(header)
+    }
+
+    fn slot(&self, config: &OAuth2Config) -> TokenSlot {
+        let key = serde_json::to_string(config).unwrap_or_default();
+
+        self.tokens.lock().unwrap().entry(key).or_default().clone()
+    }
+}
+
+async fn fetch_token(
+    client: &reqwest::Client,
+    config: &OAuth2Config,
+) -> Result<Token, Box<dyn Error>> {
+    let form = match config.grant_type {
+        GrantType::ClientCredentials => {
+            vec![("grant_type", String::from("client_credentials"))]
+        }
+        GrantType::Password => {
+            let username = config
+                .username
+                .clone()
+                .ok_or("Password grant needs a `username`")?;
+            let password = config
+                .password
+                .clone()
+                .ok_or("Password grant needs a `password`")?;
+
+            vec![
+                ("grant_type", String::from("password")),
+                ("username", username),
+                ("password", password),
+            ]
+        }
+        GrantType::RefreshToken => {
+            let refresh_token = config
+                .refresh_token
+                .as_ref()
+                .ok_or("Refresh token grant needs a `refresh_token`")?;
+
+            return refresh(client, config, refresh_token).await;
+        }
+        GrantType::AuthorizationCode => return authorize_with_pkce(client, config).await,
+    };
+
+    request_token(client, config, form).await
+}
+
+async fn refresh(
+    client: &reqwest::Client,
+    config: &OAuth2Config,
+    refresh_token: &str,
+) -> Result<Token, Box<dyn Error>> {
+    let form = vec![
+        ("grant_type", String::from("refresh_token")),
+        ("refresh_token", String::from(refresh_token)),
+    ];
+
+    let mut token = request_token(client, config, form).await?;
+
+    // Servers may keep the old refresh token valid instead of rotating it
+    if token.refresh_token.is_none() {
+        token.refresh_token = Some(String::from(refresh_token));
+    }
+
+    Ok(token)
+}
+
+async fn request_token(
+    client: &reqwest::Client,
+    config: &OAuth2Config,
+    mut form: Vec<(&str, String)>,
+) -> Result<Token, Box<dyn Error>> {
+    form.push(("client_id", config.client_id.clone()));
+
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret.clone()));
+    }
+    if let Some(scope) = &config.scope {
+        form.push(("scope", scope.clone()));
+    }
+
+    let response = client.post(&config.token_url).form(&form).send().await?;
+    let status = response.status();
+
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Token endpoint returned {}: {}", status, body))?;
+    }
+
+    let response: TokenResponse = response.json().await?;
+    log::info!("Obtained OAuth 2.0 token from {}", config.token_url);
+
+    Ok(Token::from(response))
+}
+
+async fn authorize_with_pkce(
+    client: &reqwest::Client,
+    config: &OAuth2Config,
+) -> Result<Token, Box<dyn Error>> {
+    let auth_url = config
+        .auth_url
+        .as_ref()
+        .ok_or("Authorization code grant needs an `auth_url`")?;
+
+    let verifier = random_string(64);
+    let state = random_string(16);
+
+    let listener = TcpListener::bind(("127.0.0.1", config.redirect_port)).await?;
+    let redirect_uri = format!(
+        "http://127.0.0.1:{}/callback",
+        listener.local_addr()?.port()
+    );
+
+    let mut url = Url::parse(auth_url)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &pkce_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    if let Some(scope) = &config.scope {
+        url.query_pairs_mut().append_pair("scope", scope);
+    }
+
+    log::info!("Open this URL in your browser to authorize: {}", url);
+
+    let code = tokio::time::timeout(AUTHORIZATION_TIMEOUT, wait_for_code(&listener, &state))
+        .await
+        .map_err(|_| "Timed out waiting for the authorization redirect")??;
+
+    let form = vec![
+        ("grant_type", String::from("authorization_code")),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier),
+    ];
+
+    request_token(client, config, form).await
+}
+
+// Accepts connections on the loopback listener until the browser is
+// redirected back with an authorization code
+async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String, Box<dyn Error>> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+
+        let mut buf = vec![0; 4096];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+
+        // Request line looks like "GET /callback?code=...&state=... HTTP/1.1"
+        let target = request.split_whitespace().nth(1).unwrap_or("/");
+        let url = Url::parse(&format!("http://127.0.0.1{}", target))?;
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+        // Browsers like to ask for a favicon, skip anything that is not the callback
+        if url.path() != "/callback" {
+            respond(&mut stream, "404 Not Found", "").await?;
+            continue;
+        }
+
+        if let Some(error) = params.get("error") {
+            respond(&mut stream, "400 Bad Request", "Authorization failed.").await?;
+            return Err(format!("Authorization failed: {}", error))?;
+        }
+
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond(&mut stream, "400 Bad Request", "Authorization failed.").await?;
+            return Err("Authorization redirect has a mismatched state")?;
+        }
+
+        return match params.get("code") {
+            Some(code) => {
+                respond(
+                    &mut stream,
+                    "200 OK",
+                    "Authorization complete, you can go back to postui.",
+                )
+                .await?;
+                Ok(code.clone())
+            }
+            None => {
+                respond(&mut stream, "400 Bad Request", "Authorization failed.").await?;
+                Err("Authorization redirect is missing the code")?
+            }
+        };
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers each connection with the next canned token response and records
+    // the form bodies it received
+    async fn mock_token_endpoint(
+        responses: Vec<&'static str>,
+    ) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            for body in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let form = read_body(&mut stream).await;
+                recorded.lock().unwrap().push(form);
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, received)
+    }
+
+    async fn read_body(stream: &mut TcpStream) -> String {
+        let mut data = Vec::new();
+        let mut buf = [0; 1024];
+
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+
+            let text = String::from_utf8_lossy(&data).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| {
+                        let (key, value) = line.split_once(':')?;
+                        if key.eq_ignore_ascii_case("content-length") {
+                            value.trim().parse::<usize>().ok()
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or(0);
+
+                if data.len() >= end + 4 + length {
+                    return text[end + 4..].to_string();
+                }
+            }
+
+            if n == 0 {
+                return String::new();
+            }
+        }
+    }
+
+    fn config(grant_type: GrantType, token_url: String) -> OAuth2Config {
+        OAuth2Config {
+            grant_type,
+            token_url,
+            auth_url: None,
+            client_id: String::from("postui"),
+            client_secret: Some(String::from("secret")),
+            scope: None,
+            username: Some(String::from("user")),
+            password: Some(String::from("pass")),
+            refresh_token: None,
+            redirect_port: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_token_is_cached() {
+        let (url, received) =
+            mock_token_endpoint(vec![r#"{"access_token":"abc","expires_in":3600}"#]).await;
+        let cache = TokenCache::default();
+        let client = reqwest::Client::new();
+        let config = config(GrantType::ClientCredentials, url);
+
+        let first = cache.authorization(&config, &client).await.unwrap();
+        let second = cache.authorization(&config, &client).await.unwrap();
+
+        assert_eq!(first, "Bearer abc");
+        assert_eq!(second, "Bearer abc");
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].contains("grant_type=client_credentials"));
+        assert!(received[0].contains("client_id=postui"));
+    }
+
+    #[tokio::test]
+    async fn test_same_settings_share_a_token() {
+        let (url, received) = mock_token_endpoint(vec![
+            r#"{"access_token":"abc","expires_in":3600}"#,
+            r#"{"access_token":"def","expires_in":3600}"#,
+        ])
+        .await;
+        let cache = TokenCache::default();
+        let client = reqwest::Client::new();
+        let config = config(GrantType::ClientCredentials, url);
+
+        // Another folder with the same auth
+        let first = cache.authorization(&config, &client).await.unwrap();
+        let second = cache.authorization(&config.clone(), &client).await.unwrap();
+        assert_eq!(
+            (first.as_str(), second.as_str()),
+            ("Bearer abc", "Bearer abc")
+        );
+
+        // Edited settings don't get the old token
+        let mut edited = config.clone();
+        edited.scope = Some(String::from("write"));
+        let third = cache.authorization(&edited, &client).await.unwrap();
+        assert_eq!(third, "Bearer def");
+
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_password_grant_sends_credentials() {
+        let (url, received) = mock_token_endpoint(vec![r#"{"access_token":"abc"}"#]).await;
+        let cache = TokenCache::default();
+        let client = reqwest::Client::new();
+        let config = config(GrantType::Password, url);
+
+        cache.authorization(&config, &client).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert!(received[0].contains("grant_type=password"));
+        assert!(received[0].contains("username=user"));
+        assert!(received[0].contains("password=pass"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed() {
+        let (url, received) = mock_token_endpoint(vec![
+            r#"{"access_token":"first","expires_in":0,"refresh_token":"r1"}"#,
+            r#"{"access_token":"second","expires_in":3600}"#,
+        ])
+        .await;
+        let cache = TokenCache::default();
+        let client = reqwest::Client::new();
+        let config = config(GrantType::ClientCredentials, url);
+
+        let first = cache.authorization(&config, &client).await.unwrap();
+        let second = cache.authorization(&config, &client).await.unwrap();
+
+        assert_eq!(first, "Bearer first");
+        assert_eq!(second, "Bearer second");
+
+        let received = received.lock().unwrap();
+        assert!(received[1].contains("grant_type=refresh_token"));
+        assert!(received[1].contains("refresh_token=r1"));
+    }
+
+    #[tokio::test]
+    async fn test_other_settings_dont_wait() {
+        let (url, _) = mock_token_endpoint(vec![r#"{"access_token":"abc"}"#]).await;
+        let cache = TokenCache::default();
+        let client = reqwest::Client::new();
+        let config = config(GrantType::ClientCredentials, url);
+
+        // As if a browser login for other settings was still going on
+        let mut other = config.clone();
+        other.scope = Some(String::from("admin"));
+        let slot = cache.slot(&other);
+        let _busy = slot.lock().await;
+
+        let header = tokio::time::timeout(
+            Duration::from_secs(5),
+            cache.authorization(&config, &client),
+        )
+        .await
+        .expect("waited for a token fetched with other settings")
+        .unwrap();
+
+        assert_eq!(header, "Bearer abc");
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // Example from RFC 7636, appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut favicon = TcpStream::connect(addr).await.unwrap();
+            favicon
+                .write_all(b"GET /favicon.ico HTTP/1.1\r\n\r\n")
+                .await
+                .unwrap();
+
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /callback?code=xyz&state=s1 HTTP/1.1\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(wait_for_code(&listener, "s1").await.unwrap(), "xyz");
+    }
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::http::Auth;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SigV4Config {
+    pub access_key: String,
+    pub secret_key: String,
+    pub session_token: Option<String>,
+    pub region: String,
+    pub service: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HmacConfig {
+    pub secret: String,
+    pub key_id: Option<String>,
+
+    // Header the signature is sent in
+    #[serde(default = "default_hmac_header")]
+    pub header: String,
+
+    // Request headers covered by the signature, host is always signed
+    #[serde(default)]
+    pub signed_headers: Vec<String>,
+
+    // When set, a unix timestamp is sent (and signed) in this header
+    pub timestamp_header: Option<String>,
+}
+
+fn default_hmac_header() -> String {
+    String::from("Authorization")
+}
+
+// Last canonical request that was signed, so it can be inspected in the Debug tab
+#[derive(Clone, Default)]
+pub struct CanonicalRequestLog {
+    last: Arc<Mutex<Option<String>>>,
+}
+
+impl CanonicalRequestLog {
+    pub fn set(&self, canonical_request: String) {
+        *self.last.lock().unwrap() = Some(canonical_request);
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+pub struct Signature {
+    // Headers that have to be added to the request
+    pub headers: Vec<(String, String)>,
+
+    pub canonical_request: String,
+}
+
+// Signs an already built request in place. Returns the canonical request the
+// signature was computed over, or None if the auth does not sign requests.
+pub fn sign_request(
+    request: &mut reqwest::Request,
+    auth: &Auth,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let signature = {
+        let mut headers = Vec::new();
+        for (name, value) in request.headers().iter() {
+            headers.push((String::from(name.as_str()), String::from(value.to_str()?)));
+        }
+
+        // Streaming bodies can't be hashed up front, we only ever send buffered ones
+        let body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .unwrap_or_default();
+        let method = request.method().as_str();
+        let url = request.url();
+
+        match auth {
+            Auth::AwsSigv4(config) => sigv4(config, method, url, &headers, body, Utc::now()),
+            Auth::Hmac(config) => hmac(config, method, url, &headers, body, Utc::now()),
+            _ => return Ok(None),
+        }
+    };
+
+    for (name, value) in signature.headers {
+        request.headers_mut().insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(&value)?,
+        );
+    }
+
+    Ok(Some(signature.canonical_request))
+}
+
+pub fn sigv4(
+    config: &SigV4Config,
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Signature {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let body_hash = hex::encode(Sha256::digest(body));
+
+    let mut added = vec![(String::from("x-amz-date"), amz_date.clone())];
+
+    // Only S3 insists on getting the payload hash as a header
+    if config.service == "s3" {
+        added.push((String::from("x-amz-content-sha256"), body_hash.clone()));
+    }
+    if let Some(token) = &config.session_token {
+        added.push((String::from("x-amz-security-token"), token.clone()));
+    }
+
+    let mut signed = headers.to_vec();
+    signed.extend(added.iter().cloned());
+    signed.push((String::from("host"), host(url)));
+
+    // Every service but S3 wants the path segments encoded twice
+    let uri = canonical_uri(url, config.service != "s3");
+    let (canonical_request, signed_headers) =
+        canonical_request(method, &uri, url, &signed, &body_hash);
+
+    let scope = format!("{}/{}/{}/aws4_request", date, config.region, config.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [
+        date.as_str(),
+        config.region.as_str(),
+        config.service.as_str(),
+        "aws4_request",
+    ]
+    .iter()
+    .fold(
+        format!("AWS4{}", config.secret_key).into_bytes(),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    );
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    added.push((
+        String::from("authorization"),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            config.access_key, scope, signed_headers, signature
+        ),
+    ));
+
+    Signature {
+        headers: added,
+        canonical_request,
+    }
+}
+
+pub fn hmac(
+    config: &HmacConfig,
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Signature {
+    let mut added = Vec::new();
+    let mut wanted: Vec<String> = config
+        .signed_headers
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+
+    if let Some(name) = &config.timestamp_header {
+        added.push((name.to_lowercase(), now.timestamp().to_string()));
+        wanted.push(name.to_lowercase());
+    }
+
+    let mut signed = vec![(String::from("host"), host(url))];
+    signed.extend(
+        headers
+            .iter()
+            .chain(added.iter())
+            .filter(|(name, _)| wanted.contains(&name.to_lowercase()))
+            .cloned(),
+    );
+
+    let body_hash = hex::encode(Sha256::digest(body));
+    let uri = canonical_uri(url, false);
+    let (canonical_request, signed_headers) =
+        canonical_request(method, &uri, url, &signed, &body_hash);
+    let signature = hex::encode(hmac_sha256(
+        config.secret.as_bytes(),
+        canonical_request.as_bytes(),
+    ));
+
+    let value = match &config.key_id {
+        Some(key_id) => format!(
+            "HMAC-SHA256 KeyId={}, SignedHeaders={}, Signature={}",
+            key_id, signed_headers, signature
+        ),
+        None => format!(
+            "HMAC-SHA256 SignedHeaders={}, Signature={}",
+            signed_headers, signature
+        ),
+    };
+    added.push((config.header.clone(), value));
+
+    Signature {
+        headers: added,
+        canonical_request,
+    }
+}
+
+// Builds the canonical request (in the SigV4 format) and the list of signed headers
+fn canonical_request(
+    method: &str,
+    uri: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body_hash: &str,
+) -> (String, String) {
+    let mut query = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect::<Vec<(String, String)>>();
+    query.sort();
+
+    let query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    // Repeated headers are joined into a single comma separated value
+    let mut canonical_headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+        canonical_headers
+            .entry(name.to_lowercase())
+            .or_default()
+            .push(value);
+    }
+
+    let signed_headers = canonical_headers
+        .keys()
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(";");
+
+    let canonical_headers = canonical_headers
+        .iter()
+        .map(|(name, values)| format!("{}:{}\n", name, values.join(",")))
+        .collect::<String>();
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, uri, query, canonical_headers, signed_headers, body_hash
+    );
+
+    (canonical_request, signed_headers)
+}
+
+// Path with each segment URI-encoded once, or twice with `double`, whatever
+// encoding it was written with
+fn canonical_uri(url: &Url, double: bool) -> String {
+    url.path()
+        .split('/')
+        .map(|segment| {
+            let segment = uri_encode(&percent_decode_str(segment).decode_utf8_lossy());
+
+            if double {
+                uri_encode(&segment)
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+// Same host the HTTP client will send, the port is only there when it's not the default
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => String::from(host),
+    }
+}
+
+fn uri_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2015, 8, 30).and_hms(12, 36, 0)
+    }
+
+    #[test]
+    fn test_sigv4_get_vanilla() {
+        // "get-vanilla" case from the AWS SigV4 test suite
+        let config = SigV4Config {
+            access_key: String::from("AKIDEXAMPLE"),
+            secret_key: String::from("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+            session_token: None,
+            region: String::from("us-east-1"),
+            service: String::from("service"),
+        };
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+
+        let signature = sigv4(&config, "GET", &url, &[], b"", now());
+
+        assert_eq!(
+            signature.canonical_request,
+            "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            signature.headers.last().unwrap().1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_canonical_uri() {
+        let url =
+            Url::parse("https://example.amazonaws.com/documents and settings/a%2Fb/ሴ").unwrap();
+
+        assert_eq!(
+            canonical_uri(&url, false),
+            "/documents%20and%20settings/a%2Fb/%E1%88%B4"
+        );
+        assert_eq!(
+            canonical_uri(&url, true),
+            "/documents%2520and%2520settings/a%252Fb/%25E1%2588%25B4"
+        );
+
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+        assert_eq!(canonical_uri(&url, true), "/");
+    }
+
+    #[test]
+    fn test_hmac_signs_selected_headers() {
+        let config = HmacConfig {
+            secret: String::from("secret"),
+            key_id: Some(String::from("postui")),
+            header: default_hmac_header(),
+            signed_headers: vec![String::from("Content-Type")],
+            timestamp_header: Some(String::from("X-Date")),
+        };
+        let url = Url::parse("https://example.amazonaws.com/path/to?b=x%20y&a=1").unwrap();
+        let headers = vec![
+            (
+                String::from("content-type"),
+                String::from("application/json"),
+            ),
+            (String::from("accept"), String::from("*/*")),
+        ];
+
+        let signature = hmac(&config, "POST", &url, &headers, br#"{"a":1}"#, now());
+
+        assert_eq!(
+            signature.canonical_request,
+            "POST\n/path/to\na=1&b=x%20y\ncontent-type:application/json\n\
+             host:example.amazonaws.com\nx-date:1440938160\n\ncontent-type;host;x-date\n\
+             015abd7f5cc57a2dd94b7590f04ad8084273905ee33ec5cebeae62276a97f862"
+        );
+        assert_eq!(
+            signature.headers,
+            vec![
+                (String::from("x-date"), String::from("1440938160")),
+                (
+                    String::from("Authorization"),
+                    String::from(
+                        "HMAC-SHA256 KeyId=postui, SignedHeaders=content-type;host;x-date, \
+                         Signature=801f21dc2ffe0e8e704853ff84b1a3ef6e784b3729de8541cc9b583e56523bd3"
+                    )
+                ),
+            ]
+        );
+    }
+}
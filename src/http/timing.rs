@@ -0,0 +1,137 @@
+use std::{error::Error, io::Read, time::Duration};
+
+use crate::http::connector::ConnectionTimings;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timings {
+    // Opening connections, missing when an open one was reused or the request
+    // went through a proxy. With redirects they add up over every hop
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub tls: Option<Duration>,
+
+    // From sending the request until the response headers arrived
+    pub ttfb: Duration,
+    pub download: Duration,
+}
+
+impl Timings {
+    pub fn add_connection(&mut self, connection: &ConnectionTimings) {
+        let add = |phase: Option<Duration>, d| Some(phase.unwrap_or_default() + d);
+
+        self.dns = add(self.dns, connection.dns);
+        self.connect = add(self.connect, connection.connect);
+        if let Some(tls) = connection.tls {
+            self.tls = add(self.tls, tls);
+        }
+    }
+
+    // Phases one after another, the way they happen on a fresh connection.
+    // Waiting is what's left of the time to first byte once the connection is up
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        let mut phases = Vec::new();
+        let mut connection = Duration::ZERO;
+
+        for (name, phase) in [
+            ("DNS", self.dns),
+            ("Connect", self.connect),
+            ("TLS", self.tls),
+        ] {
+            if let Some(phase) = phase {
+                phases.push((name, phase));
+                connection += phase;
+            }
+        }
+
+        phases.push(("Waiting", self.ttfb.saturating_sub(connection)));
+        phases.push(("Download", self.download));
+
+        phases
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases().iter().map(|(_, d)| *d).sum()
+    }
+}
+
+// Header sizes are estimates, the Host and Cookie headers are only added while
+// the request is sent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sizes {
+    pub request_headers: usize,
+    pub request_body: usize,
+    pub response_headers: usize,
+
+    // Body as it came over the wire and after decoding its content encoding
+    pub response_body: usize,
+    pub decoded_body: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub timings: Timings,
+    pub sizes: Sizes,
+}
+
+// Bodies are decoded here rather than by the HTTP client so their size on the
+// wire is known. Encodings that aren't known are left as they are
+pub fn decode_body(encoding: Option<&str>, body: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoded = Vec::new();
+
+    match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        Some("gzip") | Some("x-gzip") => {
+            flate2::read::GzDecoder::new(&body[..]).read_to_end(&mut decoded)?;
+        }
+        Some("deflate") => {
+            flate2::read::ZlibDecoder::new(&body[..]).read_to_end(&mut decoded)?;
+        }
+        Some("br") => {
+            brotli_decompressor::Decompressor::new(&body[..], 4096).read_to_end(&mut decoded)?;
+        }
+        _ => return Ok(body),
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    #[test]
+    fn test_phases() {
+        let timings = Timings {
+            dns: Some(Duration::from_millis(10)),
+            connect: Some(Duration::from_millis(20)),
+            tls: None,
+            ttfb: Duration::from_millis(100),
+            download: Duration::from_millis(5),
+        };
+
+        assert_eq!(
+            timings.phases(),
+            vec![
+                ("DNS", Duration::from_millis(10)),
+                ("Connect", Duration::from_millis(20)),
+                ("Waiting", Duration::from_millis(70)),
+                ("Download", Duration::from_millis(5)),
+            ]
+        );
+        assert_eq!(timings.total(), Duration::from_millis(105));
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[b'a'; 1000]).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let decoded = decode_body(Some("gzip"), compressed.clone()).unwrap();
+
+        assert!(compressed.len() < 100);
+        assert_eq!(decoded, vec![b'a'; 1000]);
+        assert_eq!(decode_body(None, vec![1, 2]).unwrap(), vec![1, 2]);
+    }
+}
@@ -0,0 +1,338 @@
+use bytes::Bytes;
+use hyper::{Body, Client};
+use reqwest::{
+    cookie::CookieStore,
+    header::{
+        HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION,
+        PROXY_AUTHORIZATION, SET_COOKIE,
+    },
+    Method, StatusCode,
+};
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::error::Error;
+use crate::http::{
+    client::RedirectPolicy,
+    connector::{ConnectionTimings, TimingConnector},
+    CookieJar, Timings,
+};
+
+// Same as reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+// A response as it came over the wire, its body still encoded
+pub struct Exchange {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    pub timings: Timings,
+}
+
+// Sends the endpoint requests over connections opened by the timing connector,
+// so each response knows how long setting up its connection took. Redirects
+// and cookies are handled the way reqwest handles them for everything else
+#[derive(Clone)]
+pub struct Transport {
+    client: Client<TimingConnector, Body>,
+    redirect: Option<RedirectPolicy>,
+    timeout: Option<Duration>,
+}
+
+impl Transport {
+    pub fn new(
+        client: Client<TimingConnector, Body>,
+        redirect: Option<RedirectPolicy>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            client,
+            redirect,
+            timeout,
+        }
+    }
+
+    pub async fn send(
+        &self,
+        request: reqwest::Request,
+        cookies: &CookieJar,
+    ) -> Result<Exchange, Error> {
+        match self.timeout {
+            Some(limit) => tokio::time::timeout(limit, self.follow(request, cookies))
+                .await
+                .map_err(|_| Error::Http(String::from("The request timed out")))?,
+            None => self.follow(request, cookies).await,
+        }
+    }
+
+    async fn follow(
+        &self,
+        request: reqwest::Request,
+        cookies: &CookieJar,
+    ) -> Result<Exchange, Error> {
+        let mut method = request.method().clone();
+        let mut url = request.url().clone();
+        let mut headers = request.headers().clone();
+        let mut body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map(Bytes::copy_from_slice)
+            .unwrap_or_default();
+
+        let max_redirects = match self.redirect {
+            Some(RedirectPolicy::Limit(max)) => max,
+            Some(RedirectPolicy::Disabled) => 0,
+            Some(RedirectPolicy::Follow) | None => MAX_REDIRECTS,
+        };
+
+        let mut timings = Timings::default();
+        let mut redirects = 0;
+        let started = Instant::now();
+
+        loop {
+            let mut builder = hyper::Request::builder()
+                .method(method.clone())
+                .uri(url.as_str());
+
+            for (name, value) in headers.iter() {
+                builder = builder.header(name, value);
+            }
+
+            if !headers.contains_key(COOKIE) {
+                if let Some(cookie) = cookies.cookies(&url) {
+                    builder = builder.header(COOKIE, cookie);
+                }
+            }
+
+            let hop = builder
+                .body(Body::from(body.clone()))
+                .map_err(|e| Error::Http(e.to_string()))?;
+
+            let response = self.client.request(hop).await?;
+
+            if let Some(connection) = response
+                .extensions()
+                .get::<ConnectionTimings>()
+                .and_then(ConnectionTimings::claim)
+            {
+                timings.add_connection(connection);
+            }
+
+            cookies.set_cookies(&mut response.headers().get_all(SET_COOKIE).iter(), &url);
+
+            let next = match redirect_target(&url, response.status(), response.headers()) {
+                Some(_) if self.redirect == Some(RedirectPolicy::Disabled) => None,
+                Some(_) if redirects == max_redirects => {
+                    return Err(Error::Http(format!(
+                        "Too many redirects, stopped after {}",
+                        max_redirects
+                    )));
+                }
+                next => next,
+            };
+
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    timings.ttfb = started.elapsed();
+
+                    let (parts, response_body) = response.into_parts();
+
+                    let started = Instant::now();
+                    let body = hyper::body::to_bytes(response_body).await?;
+                    timings.download = started.elapsed();
+
+                    return Ok(Exchange {
+                        status: parts.status,
+                        headers: parts.headers,
+                        body,
+                        timings,
+                    });
+                }
+            };
+
+            // Only 307 and 308 send the same request again, the others go on
+            // without a body
+            if !matches!(
+                response.status(),
+                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+            ) {
+                if method != Method::HEAD {
+                    method = Method::GET;
+                }
+                body = Bytes::new();
+                headers.remove(CONTENT_TYPE);
+                headers.remove(CONTENT_LENGTH);
+            }
+
+            // Credentials are only for the host they were meant for
+            if next.host_str() != url.host_str()
+                || next.port_or_known_default() != url.port_or_known_default()
+            {
+                headers.remove(AUTHORIZATION);
+                headers.remove(COOKIE);
+                headers.remove(PROXY_AUTHORIZATION);
+            }
+
+            url = next;
+            redirects += 1;
+        }
+    }
+}
+
+// Where a redirect points to, resolved against the URL it came from
+fn redirect_target(url: &Url, status: StatusCode, headers: &HeaderMap) -> Option<Url> {
+    if !matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+
+    let location = headers.get(LOCATION)?.to_str().ok()?;
+
+    url.join(location).ok()
+}
+
+// Requests going through a proxy stay with reqwest, which opens the connections
+// itself, so their connection phases aren't known
+pub async fn send_with_reqwest(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+) -> Result<Exchange, Error> {
+    let started = Instant::now();
+    let response = client.execute(request).await?;
+    let ttfb = started.elapsed();
+
+    let status = response.status();
+    let headers = response.headers().clone();
+
+    let started = Instant::now();
+    let body = response.bytes().await?;
+    let download = started.elapsed();
+
+    Ok(Exchange {
+        status,
+        headers,
+        body,
+        timings: Timings {
+            ttfb,
+            download,
+            ..Timings::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{client::ProxySettings, ClientSettings, HttpClient};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+
+    // Answers the requests in order on as many connections as the client opens,
+    // and hands back what it was sent
+    async fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let mut seen = Vec::new();
+            let mut responses = responses.into_iter();
+
+            'connections: while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+
+                while let Ok(n) = stream.read(&mut buf).await {
+                    if n == 0 {
+                        continue 'connections;
+                    }
+                    seen.push(String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase());
+
+                    match responses.next() {
+                        Some(response) => stream.write_all(response.as_bytes()).await.unwrap(),
+                        None => break 'connections,
+                    }
+
+                    if responses.len() == 0 {
+                        break 'connections;
+                    }
+                }
+            }
+
+            seen
+        });
+
+        (url, handle)
+    }
+
+    fn transport(dir: &TempDir) -> (Transport, CookieJar) {
+        let cookies = CookieJar::load(dir.path().join("config.json"));
+        let client = HttpClient::new(Arc::new(CookieJar::load(dir.path().join("other.json"))));
+        let settings = ClientSettings {
+            proxy: Some(ProxySettings {
+                use_env: Some(false),
+                ..ProxySettings::default()
+            }),
+            ..ClientSettings::default()
+        };
+
+        (client.transport(&settings).unwrap().unwrap(), cookies)
+    }
+
+    #[tokio::test]
+    async fn test_connection_timings() {
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let (url, server) = serve(vec![ok, ok]).await;
+
+        let dir = TempDir::new().unwrap();
+        let (transport, cookies) = transport(&dir);
+        let request = || reqwest::Request::new(Method::GET, Url::parse(&url).unwrap());
+
+        let first = transport.send(request(), &cookies).await.unwrap();
+        assert!(first.timings.dns.is_some());
+        assert!(first.timings.connect.is_some());
+        assert!(first.timings.tls.is_none());
+        assert_eq!(&first.body[..], b"ok");
+
+        // The connection is kept open, so nothing is set up for the next one
+        let second = transport.send(request(), &cookies).await.unwrap();
+        assert!(second.timings.dns.is_none());
+        assert_eq!(second.status, 200);
+
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_with_cookies() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /home\r\nSet-Cookie: session=1\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await;
+
+        let dir = TempDir::new().unwrap();
+        let (transport, cookies) = transport(&dir);
+
+        let mut request =
+            reqwest::Request::new(Method::POST, Url::parse(&format!("{}/login", url)).unwrap());
+        *request.body_mut() = Some(reqwest::Body::from("{}"));
+
+        let response = transport.send(request, &cookies).await.unwrap();
+        assert_eq!(response.status, 200);
+
+        let seen = server.await.unwrap();
+        assert!(seen[0].starts_with("post /login"));
+        assert!(seen[1].starts_with("get /home"));
+        assert!(seen[1].contains("cookie: session=1"));
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Replaces `{{name}}` placeholders with their values, unknown ones are left as they are
+pub fn substitute(input: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut output = String::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        output.push_str(&rest[..start]);
+
+        match variables.get(rest[start + 2..end].trim()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..end + 2]),
+        }
+
+        rest = &rest[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+// Substitutes variables in every string inside a JSON value
+pub fn substitute_value(input: &Value, variables: &BTreeMap<String, String>) -> Value {
+    match input {
+        Value::String(s) => Value::String(substitute(s, variables)),
+        Value::Array(arr) => {
+            Value::Array(arr.iter().map(|v| substitute_value(v, variables)).collect())
+        }
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), substitute_value(v, variables)))
+                .collect(),
+        ),
+        _ => input.clone(),
+    }
+}
+
+// Relative endpoint URLs are appended to the inherited base URL
+pub fn resolve_url(url: &str, base_url: Option<&str>) -> String {
+    match base_url {
+        Some(base) if !url.contains("://") => {
+            if url.is_empty() {
+                String::from(base)
+            } else {
+                format!(
+                    "{}/{}",
+                    base.trim_end_matches('/'),
+                    url.trim_start_matches('/')
+                )
+            }
+        }
+        _ => String::from(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let mut variables = BTreeMap::new();
+        variables.insert(String::from("host"), String::from("localhost:3000"));
+        variables.insert(String::from("id"), String::from("7"));
+
+        assert_eq!(
+            substitute("http://{{host}}/users/{{ id }}/{{unknown}}", &variables),
+            "http://localhost:3000/users/7/{{unknown}}"
+        );
+        assert_eq!(substitute("{{unclosed", &variables), "{{unclosed");
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let base = Some("http://localhost:3000/api/");
+
+        assert_eq!(
+            resolve_url("/users", base),
+            "http://localhost:3000/api/users"
+        );
+        assert_eq!(resolve_url("https://other/x", base), "https://other/x");
+        assert_eq!(resolve_url("/users", None), "/users");
+    }
+}
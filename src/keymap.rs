@@ -0,0 +1,943 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::HashMap, fmt, fs, io, path::Path, str::FromStr};
+
+use crate::components::CommandType;
+use crate::error::{Error, Result};
+
+// Where a shortcut works. Shortcuts of a scope are also active in the scopes
+// below it, so they can't share keys with them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    // Everywhere, unless text is being typed
+    Global,
+    // Every list and view that has a cursor
+    Navigation,
+    // Both panes of the main tab
+    MainTab,
+    Tree,
+    MainPane,
+    // The response part of the main pane
+    Response,
+    // While typing a search, nothing else is active then
+    SearchBar,
+    Cookies,
+}
+
+impl Scope {
+    // Scopes whose shortcuts are active in this one as well
+    fn parents(self) -> &'static [Scope] {
+        match self {
+            Scope::Response => &[Scope::MainPane, Scope::MainTab, Scope::Navigation],
+            Scope::Tree | Scope::MainPane => &[Scope::MainTab, Scope::Navigation],
+            Scope::MainTab | Scope::Cookies => &[Scope::Navigation],
+            Scope::Global | Scope::Navigation | Scope::SearchBar => &[],
+        }
+    }
+
+    // Scopes of the prompts opened from this one, the help lists them after it
+    fn prompts(self) -> &'static [Scope] {
+        match self {
+            Scope::MainPane => &[Scope::SearchBar],
+            _ => &[],
+        }
+    }
+
+    // Heading of the scope's shortcuts in the help
+    pub fn title(self) -> &'static str {
+        match self {
+            Scope::Global => "Global",
+            Scope::Navigation => "Moving around",
+            Scope::MainTab => "Main tab",
+            Scope::Tree => "Collection tree",
+            Scope::MainPane => "Request pane",
+            Scope::Response => "Response",
+            Scope::SearchBar => "Search bar",
+            Scope::Cookies => "Cookies",
+        }
+    }
+
+    fn overlaps(self, other: Scope) -> bool {
+        self == other
+            || self == Scope::Global
+            || other == Scope::Global
+            || self.parents().contains(&other)
+            || other.parents().contains(&self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Shortcut {
+    Quit,
+    CommandLine,
+    Palette,
+    Help,
+    MainTab,
+    HistoryTab,
+    DebugTab,
+    CookiesTab,
+
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+
+    Method,
+
+    Collapse,
+    Expand,
+    AddEndpoint,
+    FilterTree,
+
+    FocusTree,
+    Send,
+    Cancel,
+    Search,
+    NextMatch,
+    PrevMatch,
+    Timings,
+    Fold,
+    FoldValue,
+    UnfoldValue,
+    ScrollLeft,
+    ScrollRight,
+    Filter,
+    Hex,
+    SaveBody,
+    EditBody,
+
+    SearchTarget,
+    SearchCase,
+    SearchRegex,
+
+    EditCookie,
+    DeleteCookie,
+}
+
+impl Shortcut {
+    // Switching to each of the tabs, shown together as one hint
+    pub const TABS: [Shortcut; 4] = [
+        Shortcut::MainTab,
+        Shortcut::HistoryTab,
+        Shortcut::DebugTab,
+        Shortcut::CookiesTab,
+    ];
+}
+
+// Name used in the keymap file, description, scope and default keys of
+// every shortcut
+const SHORTCUTS: [(Shortcut, &str, &str, Scope, &[&str]); 40] = [
+    (
+        Shortcut::Quit,
+        "quit",
+        "Quit",
+        Scope::Global,
+        &["q", "ctrl-c"],
+    ),
+    (
+        Shortcut::CommandLine,
+        "command_line",
+        "Open the command line",
+        Scope::Global,
+        &[],
+    ),
+    (
+        Shortcut::Palette,
+        "palette",
+        "Open the command palette",
+        Scope::Global,
+        &["ctrl-p"],
+    ),
+    (
+        Shortcut::Help,
+        "help",
+        "Show this help",
+        Scope::Global,
+        &["?"],
+    ),
+    (
+        Shortcut::MainTab,
+        "tab_main",
+        "Go to the Main tab",
+        Scope::Global,
+        &["1"],
+    ),
+    (
+        Shortcut::HistoryTab,
+        "tab_history",
+        "Go to the History tab",
+        Scope::Global,
+        &["2"],
+    ),
+    (
+        Shortcut::DebugTab,
+        "tab_debug",
+        "Go to the Debug tab",
+        Scope::Global,
+        &["4"],
+    ),
+    (
+        Shortcut::CookiesTab,
+        "tab_cookies",
+        "Go to the Cookies tab",
+        Scope::Global,
+        &["5"],
+    ),
+    (Shortcut::Up, "up", "Move up", Scope::Navigation, &["up"]),
+    (
+        Shortcut::Down,
+        "down",
+        "Move down",
+        Scope::Navigation,
+        &["down"],
+    ),
+    (
+        Shortcut::PageUp,
+        "page_up",
+        "Move up a page",
+        Scope::Navigation,
+        &["pageup"],
+    ),
+    (
+        Shortcut::PageDown,
+        "page_down",
+        "Move down a page",
+        Scope::Navigation,
+        &["pagedown"],
+    ),
+    (
+        Shortcut::Top,
+        "top",
+        "Go to the top",
+        Scope::Navigation,
+        &["home"],
+    ),
+    (
+        Shortcut::Bottom,
+        "bottom",
+        "Go to the bottom",
+        Scope::Navigation,
+        &["end"],
+    ),
+    (
+        Shortcut::Method,
+        "method",
+        "Change the method",
+        Scope::MainTab,
+        &["m"],
+    ),
+    (
+        Shortcut::Collapse,
+        "collapse",
+        "Fold the folder",
+        Scope::Tree,
+        &["left"],
+    ),
+    (
+        Shortcut::Expand,
+        "expand",
+        "Unfold the folder or open the endpoint",
+        Scope::Tree,
+        &["right"],
+    ),
+    (
+        Shortcut::AddEndpoint,
+        "add_endpoint",
+        "Add an endpoint",
+        Scope::Tree,
+        &["a"],
+    ),
+    (
+        Shortcut::FilterTree,
+        "filter_tree",
+        "Filter the endpoints",
+        Scope::Tree,
+        &["/"],
+    ),
+    (
+        Shortcut::FocusTree,
+        "focus_tree",
+        "Go back to the tree",
+        Scope::MainPane,
+        &["left"],
+    ),
+    (
+        Shortcut::Send,
+        "send",
+        "Send the request",
+        Scope::MainPane,
+        &["s"],
+    ),
+    (
+        Shortcut::Cancel,
+        "cancel",
+        "Cancel the request",
+        Scope::MainPane,
+        &["c"],
+    ),
+    (
+        Shortcut::Search,
+        "search",
+        "Search the response",
+        Scope::MainPane,
+        &["/"],
+    ),
+    (
+        Shortcut::NextMatch,
+        "next_match",
+        "Next match",
+        Scope::MainPane,
+        &["n"],
+    ),
+    (
+        Shortcut::PrevMatch,
+        "prev_match",
+        "Previous match",
+        Scope::MainPane,
+        &["N"],
+    ),
+    (
+        Shortcut::Timings,
+        "timings",
+        "Show or hide the timings",
+        Scope::MainPane,
+        &["t"],
+    ),
+    (
+        Shortcut::Fold,
+        "fold",
+        "Fold or unfold the JSON under the cursor",
+        Scope::Response,
+        &["enter", "space"],
+    ),
+    (
+        Shortcut::FoldValue,
+        "fold_value",
+        "Fold the JSON under the cursor, or go to the tree",
+        Scope::Response,
+        &[],
+    ),
+    (
+        Shortcut::UnfoldValue,
+        "unfold_value",
+        "Unfold the JSON under the cursor",
+        Scope::Response,
+        &[],
+    ),
+    (
+        Shortcut::ScrollLeft,
+        "scroll_left",
+        "Scroll the response left",
+        Scope::Response,
+        &["<", "shift-left"],
+    ),
+    (
+        Shortcut::ScrollRight,
+        "scroll_right",
+        "Scroll the response right",
+        Scope::Response,
+        &[">", "shift-right"],
+    ),
+    (
+        Shortcut::Filter,
+        "filter",
+        "Filter the response with jq",
+        Scope::Response,
+        &["f"],
+    ),
+    (
+        Shortcut::Hex,
+        "hex",
+        "Show the body as hex",
+        Scope::Response,
+        &["x"],
+    ),
+    (
+        Shortcut::SaveBody,
+        "save_body",
+        "Save the response body to a file",
+        Scope::Response,
+        &["w"],
+    ),
+    (
+        Shortcut::EditBody,
+        "edit_body",
+        "Edit the request body",
+        Scope::MainPane,
+        &["e"],
+    ),
+    (
+        Shortcut::SearchTarget,
+        "search_target",
+        "Search the response or the request body",
+        Scope::SearchBar,
+        &["tab"],
+    ),
+    (
+        Shortcut::SearchCase,
+        "search_case",
+        "Make the search case-sensitive or not",
+        Scope::SearchBar,
+        &["ctrl-t"],
+    ),
+    (
+        Shortcut::SearchRegex,
+        "search_regex",
+        "Read the search as a regex or as text",
+        Scope::SearchBar,
+        &["ctrl-r"],
+    ),
+    (
+        Shortcut::EditCookie,
+        "edit_cookie",
+        "Edit the cookie's value",
+        Scope::Cookies,
+        &["e", "enter"],
+    ),
+    (
+        Shortcut::DeleteCookie,
+        "delete_cookie",
+        "Delete the cookie",
+        Scope::Cookies,
+        &["d", "delete"],
+    ),
+];
+
+// Keys of the vim preset, replacing the defaults of these shortcuts. Digits
+// are counts there, so the tabs move to alt.
+const VIM_SHORTCUTS: [(Shortcut, &[&str]); 16] = [
+    (Shortcut::MainTab, &["alt-1"]),
+    (Shortcut::HistoryTab, &["alt-2"]),
+    (Shortcut::DebugTab, &["alt-4"]),
+    (Shortcut::CookiesTab, &["alt-5"]),
+    (Shortcut::CommandLine, &[":"]),
+    (Shortcut::Up, &["k", "up"]),
+    (Shortcut::Down, &["j", "down"]),
+    (Shortcut::PageUp, &["ctrl-u", "pageup"]),
+    (Shortcut::PageDown, &["ctrl-d", "pagedown"]),
+    (Shortcut::Bottom, &["G", "end"]),
+    (Shortcut::Collapse, &["h", "left"]),
+    (Shortcut::Expand, &["l", "right"]),
+    (Shortcut::FocusTree, &["left"]),
+    (Shortcut::FoldValue, &["h"]),
+    (Shortcut::UnfoldValue, &["l"]),
+    (Shortcut::EditBody, &["i"]),
+];
+
+// A key together with the modifiers held down with it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn matches(&self, ev: &KeyEvent) -> bool {
+        // Shift is already in the character itself
+        let mut modifiers = ev.modifiers;
+        if let KeyCode::Char(_) | KeyCode::BackTab = ev.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        self.code == ev.code && self.modifiers == modifiers
+    }
+
+    pub fn event(&self) -> KeyEvent {
+        KeyEvent::new(self.code, self.modifiers)
+    }
+}
+
+// Keys are written like "q", "N", "ctrl-c", "alt+enter", "f5" or "pagedown"
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.trim();
+
+        'prefixes: loop {
+            for (name, modifier) in [
+                ("ctrl", KeyModifiers::CONTROL),
+                ("alt", KeyModifiers::ALT),
+                ("shift", KeyModifiers::SHIFT),
+            ] {
+                let prefixed = rest.len() > name.len() + 1
+                    && rest[..name.len()].eq_ignore_ascii_case(name)
+                    && matches!(rest.as_bytes()[name.len()], b'-' | b'+');
+
+                if prefixed {
+                    modifiers.insert(modifier);
+                    rest = &rest[name.len() + 1..];
+                    continue 'prefixes;
+                }
+            }
+            break;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                f => match f.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key {:?}", s)),
+                },
+            },
+        };
+
+        // Events carry shifted letters and shift-tab as keys of their own
+        let code = match (code, modifiers.contains(KeyModifiers::SHIFT)) {
+            (KeyCode::Tab, true) => KeyCode::BackTab,
+            (KeyCode::Char(c), true) => KeyCode::Char(c.to_ascii_uppercase()),
+            (code, _) => code,
+        };
+        if let KeyCode::Char(_) | KeyCode::BackTab = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        Ok(KeyChord { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "⏎"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "del"),
+            KeyCode::Insert => write!(f, "ins"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pgup"),
+            KeyCode::PageDown => write!(f, "pgdn"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+// Shortcuts of one scope as listed in the help, keys next to what they do
+#[derive(Clone, Debug, PartialEq)]
+pub struct HelpSection {
+    pub title: &'static str,
+    pub keys: Vec<(String, &'static str)>,
+}
+
+// Keys of every shortcut, the defaults overridden by the keymap file
+pub struct Keymap {
+    bindings: HashMap<Shortcut, Vec<KeyChord>>,
+
+    // Counts, `gg`, `gt` and the command line on top of the keys
+    vim: bool,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = SHORTCUTS
+            .iter()
+            .map(|(shortcut, _, _, _, keys)| (*shortcut, chords(keys)))
+            .collect();
+
+        Keymap {
+            bindings,
+            vim: false,
+        }
+    }
+}
+
+fn chords(keys: &[&str]) -> Vec<KeyChord> {
+    keys.iter().filter_map(|key| key.parse().ok()).collect()
+}
+
+impl Keymap {
+    // The defaults when there's no keymap file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(input) => Self::from_toml(&input),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn vim() -> Self {
+        let mut keymap = Self::default();
+
+        for (shortcut, keys) in VIM_SHORTCUTS.iter() {
+            keymap.bindings.insert(*shortcut, chords(keys));
+        }
+        keymap.vim = true;
+
+        keymap
+    }
+
+    // A table of shortcut names and their keys, either one key or a list,
+    // optionally starting from the vim preset:
+    //
+    //   vim = true
+    //   send = "ctrl-s"
+    //   quit = ["q", "ctrl-q"]
+    pub fn from_toml(input: &str) -> Result<Self> {
+        let table: toml::value::Table =
+            toml::from_str(input).map_err(|e| Error::Config(format!("Invalid keymap: {}", e)))?;
+
+        let mut keymap = match table.get("vim") {
+            None | Some(toml::Value::Boolean(false)) => Self::default(),
+            Some(toml::Value::Boolean(true)) => Self::vim(),
+            Some(_) => return Err(Error::Config(String::from("vim has to be true or false"))),
+        };
+
+        for (name, value) in table.iter().filter(|(name, _)| *name != "vim") {
+            let shortcut = Self::shortcut(name)
+                .ok_or_else(|| Error::Config(format!("Unknown shortcut {:?}", name)))?;
+
+            let keys = match value {
+                toml::Value::String(key) => vec![key.as_str()],
+                toml::Value::Array(keys) => keys.iter().filter_map(|k| k.as_str()).collect(),
+                _ => Vec::new(),
+            };
+
+            let chords = keys
+                .iter()
+                .map(|key| key.parse())
+                .collect::<std::result::Result<Vec<KeyChord>, String>>()
+                .map_err(|e| Error::Config(format!("{} for {}", e, name)))?;
+
+            keymap.bindings.insert(shortcut, chords);
+        }
+
+        keymap.validate()?;
+
+        Ok(keymap)
+    }
+
+    // Two shortcuts that are active at the same time can't share a key
+    fn validate(&self) -> Result<()> {
+        for (i, (a, a_name, _, a_scope, _)) in SHORTCUTS.iter().enumerate() {
+            for (b, b_name, _, b_scope, _) in SHORTCUTS[i + 1..].iter() {
+                if !a_scope.overlaps(*b_scope) {
+                    continue;
+                }
+
+                if let Some(chord) = self.chords(*a).iter().find(|c| self.chords(*b).contains(c)) {
+                    return Err(Error::Config(format!(
+                        "{} is bound to both {} and {}",
+                        chord, a_name, b_name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_vim(&self) -> bool {
+        self.vim
+    }
+
+    // Shortcut by its name in the keymap file
+    pub fn shortcut(name: &str) -> Option<Shortcut> {
+        SHORTCUTS
+            .iter()
+            .find(|(_, n, _, _, _)| *n == name)
+            .map(|(shortcut, _, _, _, _)| *shortcut)
+    }
+
+    // Scope the shortcut works in
+    pub fn scope(shortcut: Shortcut) -> Scope {
+        SHORTCUTS
+            .iter()
+            .find(|(s, _, _, _, _)| *s == shortcut)
+            .map_or(Scope::Global, |(_, _, _, scope, _)| *scope)
+    }
+
+    // Every shortcut that has a key, with its description and first key, for
+    // running them from the command palette
+    // The search bar's keys only do something while it's being typed in, so
+    // they aren't offered here
+    pub fn commands(&self) -> Vec<(Shortcut, &'static str, String)> {
+        SHORTCUTS
+            .iter()
+            .filter(|(_, _, _, scope, _)| *scope != Scope::SearchBar)
+            .filter(|(shortcut, _, _, _, _)| !self.chords(*shortcut).is_empty())
+            .map(|(shortcut, _, description, _, _)| {
+                (*shortcut, *description, self.keys(&[*shortcut]))
+            })
+            .collect()
+    }
+
+    // Key that triggers the shortcut, for running it without pressing it
+    pub fn key_event(&self, shortcut: Shortcut) -> Option<KeyEvent> {
+        self.chords(shortcut).first().map(KeyChord::event)
+    }
+
+    fn chords(&self, shortcut: Shortcut) -> &[KeyChord] {
+        self.bindings
+            .get(&shortcut)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // Shortcut the key stands for in the given scope
+    pub fn lookup(&self, ev: KeyEvent, scope: Scope) -> Option<Shortcut> {
+        SHORTCUTS
+            .iter()
+            .filter(|(_, _, _, s, _)| *s == scope || scope.parents().contains(s))
+            .map(|(shortcut, _, _, _, _)| *shortcut)
+            .find(|shortcut| self.chords(*shortcut).iter().any(|c| c.matches(&ev)))
+    }
+
+    // Every key that works in the scope, grouped by the scope each shortcut
+    // belongs to, from the most general to the scope itself. Shortcuts
+    // without any key are left out.
+    pub fn help(&self, scope: Option<Scope>) -> Vec<HelpSection> {
+        let mut scopes = vec![Scope::Global];
+        if let Some(scope) = scope {
+            scopes.extend(scope.parents().iter().rev());
+            scopes.push(scope);
+        }
+        for scope in scopes.clone() {
+            scopes.extend(scope.prompts());
+        }
+
+        scopes
+            .into_iter()
+            .map(|scope| {
+                let keys = SHORTCUTS
+                    .iter()
+                    .filter(|(shortcut, _, _, s, _)| {
+                        *s == scope && !self.chords(*shortcut).is_empty()
+                    })
+                    .map(|(shortcut, _, description, _, _)| {
+                        let keys = self
+                            .chords(*shortcut)
+                            .iter()
+                            .map(KeyChord::to_string)
+                            .collect::<Vec<String>>()
+                            .join(" ");
+
+                        (keys, *description)
+                    })
+                    .collect();
+
+                HelpSection {
+                    title: scope.title(),
+                    keys,
+                }
+            })
+            .collect()
+    }
+
+    // First key of each of the shortcuts, for showing them to the user
+    pub fn keys(&self, shortcuts: &[Shortcut]) -> String {
+        shortcuts
+            .iter()
+            .filter_map(|shortcut| self.chords(*shortcut).first())
+            .map(KeyChord::to_string)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    // Command bar entry like "Send [s]"
+    pub fn hint(&self, label: &str, shortcuts: &[Shortcut]) -> CommandType {
+        CommandType::Command(format!("{} [{}]", label, self.keys(shortcuts)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key_chords() {
+        let chord = |s: &str| s.parse::<KeyChord>().unwrap();
+
+        assert!(chord("ctrl-c").matches(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert!(!chord("ctrl-c").matches(&key(KeyCode::Char('c'), KeyModifiers::NONE)));
+        assert!(chord("N").matches(&key(KeyCode::Char('N'), KeyModifiers::SHIFT)));
+        assert!(chord("shift-n").matches(&key(KeyCode::Char('N'), KeyModifiers::SHIFT)));
+        assert!(chord("Alt+Enter").matches(&key(KeyCode::Enter, KeyModifiers::ALT)));
+        assert!(chord("shift-tab").matches(&key(KeyCode::BackTab, KeyModifiers::SHIFT)));
+        assert!(chord("-").matches(&key(KeyCode::Char('-'), KeyModifiers::NONE)));
+        assert_eq!(chord("f5").code, KeyCode::F(5));
+
+        assert!("ctrl-".parse::<KeyChord>().is_err());
+        assert!("f13".parse::<KeyChord>().is_err());
+        assert!("hyper-x".parse::<KeyChord>().is_err());
+
+        assert_eq!(chord("ctrl+s").to_string(), "ctrl-s");
+        assert_eq!(chord("right").to_string(), "→");
+    }
+
+    #[test]
+    fn test_keymap_file() {
+        let keymap = Keymap::default();
+        assert!(keymap.validate().is_ok());
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Left, KeyModifiers::NONE), Scope::Tree),
+            Some(Shortcut::Collapse)
+        );
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Char('m'), KeyModifiers::NONE), Scope::MainPane),
+            Some(Shortcut::Method)
+        );
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Char('s'), KeyModifiers::NONE), Scope::Tree),
+            None
+        );
+
+        let keymap = Keymap::from_toml("send = \"ctrl-s\"\nquit = [\"ctrl-q\"]").unwrap();
+        assert_eq!(
+            keymap.lookup(
+                key(KeyCode::Char('s'), KeyModifiers::CONTROL),
+                Scope::MainPane
+            ),
+            Some(Shortcut::Send)
+        );
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Char('q'), KeyModifiers::NONE), Scope::Global),
+            None
+        );
+        assert!(matches!(
+            keymap.hint("Send", &[Shortcut::Send, Shortcut::Cancel]),
+            CommandType::Command(hint) if hint == "Send [ctrl-s c]"
+        ));
+
+        // Same key in panes that are never active together is fine
+        assert!(Keymap::from_toml("add_endpoint = \"s\"").is_ok());
+
+        let e = Keymap::from_toml("send = \"f\"").err().unwrap();
+        assert_eq!(e.to_string(), "f is bound to both send and filter");
+
+        let e = Keymap::from_toml("hex = \"1\"").err().unwrap();
+        assert_eq!(e.to_string(), "1 is bound to both tab_main and hex");
+
+        let keymap = Keymap::from_toml("vim = true\nfold = \"z\"").unwrap();
+        assert!(keymap.is_vim());
+        assert!(Keymap::vim().validate().is_ok());
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Char('j'), KeyModifiers::NONE), Scope::Cookies),
+            Some(Shortcut::Down)
+        );
+        assert_eq!(
+            keymap.lookup(key(KeyCode::Char('1'), KeyModifiers::NONE), Scope::Global),
+            None
+        );
+        assert_eq!(keymap.keys(&[Shortcut::Fold]), "z");
+        assert!(Keymap::from_toml("vim = \"yes\"").is_err());
+        assert!(Keymap::from_toml("vim = true\nsend = \"j\"").is_err());
+
+        assert!(Keymap::from_toml("sned = \"s\"").is_err());
+        assert!(Keymap::from_toml("send = \"ctrl-\"").is_err());
+        assert!(Keymap::from_toml("send = ").is_err());
+    }
+
+    #[test]
+    fn test_help() {
+        let keymap = Keymap::from_toml("send = [\"s\", \"ctrl-s\"]").unwrap();
+
+        let help = keymap.help(Some(Scope::MainPane));
+        let titles = help.iter().map(|section| section.title).collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            [
+                "Global",
+                "Moving around",
+                "Main tab",
+                "Request pane",
+                "Search bar"
+            ]
+        );
+        assert!(help[4].keys.contains(&(
+            String::from("ctrl-r"),
+            "Read the search as a regex or as text"
+        )));
+        assert!(help[3]
+            .keys
+            .contains(&(String::from("s ctrl-s"), "Send the request")));
+
+        // The command line has no key outside of vim
+        assert!(help[0]
+            .keys
+            .iter()
+            .all(|(_, d)| *d != "Open the command line"));
+
+        // Folding with h and l is listed for the response in vim
+        let help = Keymap::vim().help(Some(Scope::Response));
+        assert_eq!(help[4].title, "Response");
+        assert!(help[4].keys.contains(&(
+            String::from("h"),
+            "Fold the JSON under the cursor, or go to the tree"
+        )));
+        assert!(help[4]
+            .keys
+            .contains(&(String::from("l"), "Unfold the JSON under the cursor")));
+        assert!(help[3]
+            .keys
+            .contains(&(String::from("←"), "Go back to the tree")));
+
+        let help = keymap.help(None);
+        assert_eq!(help.len(), 1);
+        assert!(help[0]
+            .keys
+            .contains(&(String::from("?"), "Show this help")));
+    }
+
+    #[test]
+    fn test_commands() {
+        let keymap = Keymap::from_toml("send = [\"ctrl-s\", \"s\"]").unwrap();
+        let commands = keymap.commands();
+
+        // Everything but the command line and folding with h and l, which
+        // have no keys outside of vim, and the search bar's keys
+        assert_eq!(commands.len(), SHORTCUTS.len() - 6);
+        assert!(commands.contains(&(Shortcut::Send, "Send the request", String::from("ctrl-s"))));
+        assert!(commands.iter().all(|(s, _, _)| *s != Shortcut::CommandLine));
+        assert!(Keymap::vim()
+            .commands()
+            .iter()
+            .any(|(s, _, _)| *s == Shortcut::CommandLine));
+
+        assert_eq!(Keymap::scope(Shortcut::DeleteCookie), Scope::Cookies);
+        assert_eq!(Keymap::scope(Shortcut::Expand), Scope::Tree);
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use slog::Drain;
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, TryLockError},
+};
+
+// How many of the last log lines are kept for a crash report
+const KEPT_LINES: usize = 100;
+
+// Sends every record on to the debug tab and keeps the last lines as text on
+// the side. The crash report reads them from here, the debug tab's logger
+// can't be looked into without taking its lock
+struct Logger {
+    lines: Mutex<VecDeque<String>>,
+}
+
+static LOGGER: Logger = Logger {
+    lines: Mutex::new(VecDeque::new()),
+};
+
+pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_max_level(level);
+    log::set_logger(&LOGGER)
+}
+
+// The lines kept so far, oldest first. Nothing when they are being written
+// right now, as this runs while panicking and must not wait for anything
+pub fn last_lines() -> Vec<String> {
+    let lines = match LOGGER.lines.try_lock() {
+        Ok(lines) => lines,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return Vec::new(),
+    };
+
+    lines.iter().cloned().collect()
+}
+
+impl Log for Logger {
+    // The debug tab filters by its own levels
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let line = format!(
+            "{}:{}:{}:{}",
+            Local::now().format("%H:%M:%S"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == KEPT_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+
+        // tui-logger can't be handed a record directly, only through its slog drain
+        let location = slog::RecordLocation {
+            file: record.file_static().unwrap_or_default(),
+            line: record.line().unwrap_or_default(),
+            column: 0,
+            function: "",
+            module: record.module_path_static().unwrap_or_default(),
+        };
+        let level = match record.level() {
+            log::Level::Error => slog::Level::Error,
+            log::Level::Warn => slog::Level::Warning,
+            log::Level::Info => slog::Level::Info,
+            log::Level::Debug => slog::Level::Debug,
+            log::Level::Trace => slog::Level::Trace,
+        };
+        let statics = slog::RecordStatic {
+            location: &location,
+            tag: record.target(),
+            level,
+        };
+
+        let _ = tui_logger::slog_drain().log(
+            &slog::Record::new(&statics, record.args(), slog::b!()),
+            &slog::OwnedKVList::from(slog::o!()),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_lines() {
+        let logger = Logger {
+            lines: Mutex::new(VecDeque::new()),
+        };
+
+        for i in 0..KEPT_LINES + 5 {
+            logger.log(
+                &Record::builder()
+                    .level(log::Level::Info)
+                    .target("postui::app")
+                    .args(format_args!("line {}", i))
+                    .build(),
+            );
+        }
+
+        let lines = logger.lines.lock().unwrap();
+        assert_eq!(lines.len(), KEPT_LINES);
+        assert!(lines[0].ends_with(":INFO:postui::app:line 5"));
+    }
+}
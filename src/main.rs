@@ -1,25 +1,51 @@
 use crossterm::{
-    event::{self, Event},
+    event::{Event, EventStream},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use log;
-use std::{
-    io,
-    time::{Duration, Instant},
+    terminal::{enable_raw_mode, EnterAlternateScreen},
 };
+use futures::StreamExt;
+use std::{io, time::Duration};
+use tokio::{sync::mpsc, time::MissedTickBehavior};
 use tui::{backend::Backend, backend::CrosstermBackend, Terminal};
-use tui_logger;
 
 mod app;
 mod components;
+mod crash;
+mod error;
+mod events;
+mod filter;
 mod foldertree;
+mod fuzzy;
+mod headless;
+mod http;
+mod keymap;
+mod logger;
 mod tabs;
+mod theme;
 
 use crate::app::*;
+use crate::components::Component;
 
 #[tokio::main]
 async fn main() -> Result<(), io::Error> {
+    // Any argument means there's no UI to start
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    if !args.is_empty() {
+        if let Err(e) = headless::run(&args).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    // Set up logger
+    logger::init(log::LevelFilter::Debug).unwrap();
+    tui_logger::set_default_level(log::LevelFilter::Trace);
+
+    crash::install_panic_hook();
+
     enable_raw_mode()?;
 
     // Set up terminal output
@@ -28,55 +54,64 @@ async fn main() -> Result<(), io::Error> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let tick_rate = Duration::from_millis(200);
-    let mut last_tick = Instant::now();
+    let result = run(&mut terminal).await;
+
+    crash::restore_terminal()?;
+
+    result
+}
+
+async fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    // Only used while something animates, like the spinner of a running request
+    let mut ticks = tokio::time::interval(Duration::from_millis(100));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-    let mut app = App::new();
+    let (tx, mut messages) = mpsc::unbounded_channel();
+    let mut terminal_events = EventStream::new();
+
+    let mut app = App::new(tx.clone());
+    events::watch_file(COLLECTION_PATH, tx);
 
     // Clear the terminal before first draw.
     terminal.clear()?;
 
-    // Set up logger
-    tui_logger::init_logger(log::LevelFilter::Debug).unwrap();
-    tui_logger::set_default_level(log::LevelFilter::Trace);
-
     log::info!("App initialized");
 
-    loop {
-        draw(&mut terminal, &mut app)?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+    // The screen is only drawn again after something changed
+    let mut dirty = true;
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(event) = event::read()? {
-                app.event(event);
-            }
+    loop {
+        if dirty {
+            draw(terminal, &mut app)?;
+            dirty = false;
         }
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+        tokio::select! {
+            event = terminal_events.next() => match event {
+                Some(Ok(Event::Key(event))) => {
+                    app.event(event);
+                    dirty = true;
+                }
+                Some(Ok(Event::Resize(_, _))) => dirty = true,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            Some(message) = messages.recv() => {
+                app.message(message);
+                dirty = true;
+            }
+            _ = ticks.tick(), if app.is_animating() => dirty = true,
         }
 
         if app.is_quit() {
-            break;
+            return Ok(());
         }
     }
-
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    terminal.show_cursor()?;
-
-    Ok(())
 }
 
 fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
-    terminal.draw(|frame| {
-        if let Err(e) = app.draw(frame) {
-            panic!("failed to draw the app: {}", e);
-        }
-    })?;
+    terminal.draw(|frame| app.draw(frame, frame.size()))?;
 
     Ok(())
 }
@@ -0,0 +1,232 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::sync::Arc;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::components::{Action, CommandType, Component};
+use crate::http::{CookieEntry, CookieJar};
+use crate::keymap::{Keymap, Scope, Shortcut};
+use crate::theme::Theme;
+
+pub struct CookiesTab<'a> {
+    cookies: Arc<CookieJar>,
+    entries: Vec<CookieEntry>,
+    state: TableState,
+
+    // Value editor, only there while a cookie is being edited
+    editor: Option<TextArea<'a>>,
+
+    // Rows that fit in the table, as of the last draw
+    height: usize,
+
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+}
+
+impl CookiesTab<'_> {
+    pub fn new(cookies: Arc<CookieJar>, keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        let mut state = TableState::default();
+        state.select(Some(0));
+
+        Self {
+            cookies,
+            entries: Vec::new(),
+            state,
+            editor: None,
+            height: 0,
+            keymap,
+            theme,
+        }
+    }
+
+    fn selected(&self) -> Option<CookieEntry> {
+        self.state
+            .selected()
+            .and_then(|i| self.entries.get(i))
+            .cloned()
+    }
+
+    fn next(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let i = match self.state.selected() {
+            Some(i) if i + 1 < self.entries.len() => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    fn previous(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let i = match self.state.selected() {
+            Some(0) | None => self.entries.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    // Moves by that many rows without wrapping around
+    fn move_by(&mut self, delta: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let last = self.entries.len() as isize - 1;
+        let i = (self.state.selected().unwrap_or(0) as isize).saturating_add(delta);
+        self.state.select(Some(i.clamp(0, last) as usize));
+    }
+
+    fn clamp_selection(&mut self) {
+        if let Some(i) = self.state.selected() {
+            if i >= self.entries.len() {
+                self.state
+                    .select(Some(self.entries.len().saturating_sub(1)));
+            }
+        }
+    }
+}
+
+impl Component for CookiesTab<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        self.entries = self.cookies.entries();
+
+        let selected = self.selected();
+
+        if let Some(editor) = &mut self.editor {
+            match ev.code {
+                KeyCode::Esc => self.editor = None,
+                KeyCode::Enter => {
+                    if let Some(entry) = selected {
+                        self.cookies.set_value(&entry, &editor.lines()[0]);
+                    }
+                    self.editor = None;
+                }
+                _ => {
+                    editor.input(ev);
+                }
+            }
+            return Action::None;
+        }
+
+        match self.keymap.lookup(ev, Scope::Cookies) {
+            Some(Shortcut::Down) => self.next(),
+            Some(Shortcut::Up) => self.previous(),
+            Some(Shortcut::PageDown) => self.move_by(self.height.max(1) as isize),
+            Some(Shortcut::PageUp) => self.move_by(-(self.height.max(1) as isize)),
+            Some(Shortcut::Top) => self.move_by(isize::MIN),
+            Some(Shortcut::Bottom) => self.move_by(isize::MAX),
+            Some(Shortcut::EditCookie) => {
+                if let Some(entry) = self.selected() {
+                    let mut editor = TextArea::from([entry.value.clone()]);
+                    editor.set_block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Thick)
+                            .title(format!("Value of {}", entry.name)),
+                    );
+                    editor.move_cursor(tui_textarea::CursorMove::End);
+
+                    self.editor = Some(editor);
+                }
+            }
+            Some(Shortcut::DeleteCookie) => {
+                if let Some(entry) = self.selected() {
+                    self.cookies.remove(&entry);
+                    self.entries = self.cookies.entries();
+                }
+            }
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        self.entries = self.cookies.entries();
+        self.clamp_selection();
+
+        let editor_height = if self.editor.is_some() { 3 } else { 0 };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(editor_height)])
+            .split(r);
+
+        // Domain is only shown on the first cookie of each domain
+        let mut last_domain = "";
+        let rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let domain = if entry.domain != last_domain {
+                    entry.domain.as_str()
+                } else {
+                    ""
+                };
+                last_domain = entry.domain.as_str();
+
+                Row::new(vec![
+                    Cell::from(domain).style(Style::default().add_modifier(Modifier::BOLD)),
+                    Cell::from(entry.name.as_str()),
+                    Cell::from(entry.value.as_str()),
+                    Cell::from(entry.path.as_str()),
+                    Cell::from(entry.expires.as_deref().unwrap_or("session")),
+                ])
+            })
+            .collect::<Vec<Row>>();
+
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Domain", "Name", "Value", "Path", "Expires"])
+                    .style(self.theme.fg(self.theme.heading)),
+            )
+            .block(Block::default().borders(Borders::ALL).title("Cookies"))
+            .widths(&[
+                Constraint::Percentage(20),
+                Constraint::Percentage(15),
+                Constraint::Percentage(40),
+                Constraint::Percentage(10),
+                Constraint::Percentage(15),
+            ])
+            .highlight_style(self.theme.selected().add_modifier(Modifier::BOLD));
+
+        // Borders and the header take up three rows
+        self.height = chunks[0].height.saturating_sub(3) as usize;
+        f.render_stateful_widget(table, chunks[0], &mut self.state);
+
+        if let Some(editor) = &self.editor {
+            f.render_widget(editor.widget(), chunks[1]);
+        }
+    }
+
+    fn commands(&self) -> Vec<CommandType> {
+        vec![
+            self.keymap.hint("Edit", &[Shortcut::EditCookie]),
+            CommandType::Break,
+            self.keymap.hint("Delete", &[Shortcut::DeleteCookie]),
+            CommandType::Break,
+            self.keymap.hint("Tabs", &Shortcut::TABS),
+            CommandType::Break,
+            self.keymap.hint("Help", &[Shortcut::Help]),
+        ]
+    }
+
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Cookies)
+    }
+
+    fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+}
@@ -1,39 +1,73 @@
+use crossterm::event::KeyEvent;
+use std::sync::Arc;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 use tui_logger::TuiLoggerWidget;
 
-pub struct DebugTab {}
+use crate::components::{Action, Component};
+use crate::http::CanonicalRequestLog;
+use crate::theme::Theme;
+
+pub struct DebugTab {
+    canonical_requests: CanonicalRequestLog,
+    theme: Arc<Theme>,
+}
 
 impl DebugTab {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(canonical_requests: CanonicalRequestLog, theme: Arc<Theme>) -> Self {
+        Self {
+            canonical_requests,
+            theme,
+        }
+    }
+}
+
+impl Component for DebugTab {
+    fn event(&mut self, _ev: KeyEvent) -> Action {
+        Action::None
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        let canonical_request = self.canonical_requests.get();
+
+        let constraints = if canonical_request.is_some() {
+            [Constraint::Percentage(60), Constraint::Percentage(40)]
+        } else {
+            [Constraint::Percentage(100), Constraint::Percentage(0)]
+        };
+
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(100)])
+            .constraints(constraints)
             .split(r);
 
+        let theme = &self.theme;
+
         let temp_block = TuiLoggerWidget::default()
-            .style_error(Style::default().fg(Color::Red))
-            .style_debug(Style::default().fg(Color::Green))
-            .style_warn(Style::default().fg(Color::Yellow))
-            .style_trace(Style::default().fg(Color::Gray))
-            .style_info(Style::default().fg(Color::Blue))
-            .block(
-                Block::default()
-                    .title("Logs")
-                    .border_style(Style::default().fg(Color::White).bg(Color::Black))
-                    .borders(Borders::ALL),
-            )
-            .style(Style::default().fg(Color::White).bg(Color::Black));
+            .style_error(theme.fg(theme.error))
+            .style_debug(theme.fg(theme.success))
+            .style_warn(theme.fg(theme.warning))
+            .style_trace(theme.fg(theme.muted))
+            .style_info(theme.fg(theme.info))
+            .block(Block::default().title("Logs").borders(Borders::ALL));
 
         f.render_widget(temp_block, chunks[0]);
+
+        // Shows what the last signed request's signature was computed over
+        if let Some(canonical_request) = canonical_request {
+            let canonical = Paragraph::new(canonical_request)
+                .block(
+                    Block::default()
+                        .title("Canonical request")
+                        .borders(Borders::ALL),
+                )
+                .wrap(Wrap { trim: false });
+
+            f.render_widget(canonical, chunks[1]);
+        }
     }
 }
@@ -1,24 +1,96 @@
+use crossterm::event::KeyEvent;
+use std::sync::Arc;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    widgets::{Block, Borders},
+    widgets::{Block, Borders, Cell, Row, Table},
     Frame,
 };
 
-pub struct HistoryTab {}
+use crate::components::{
+    format::{format_elapsed, format_size},
+    Action, CommandType, Component,
+};
+use crate::http::History;
+use crate::keymap::{Keymap, Shortcut};
+use crate::theme::Theme;
+
+pub struct HistoryTab {
+    history: History,
+    keymap: Arc<Keymap>,
+    theme: Arc<Theme>,
+}
 
 impl HistoryTab {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(history: History, keymap: Arc<Keymap>, theme: Arc<Theme>) -> Self {
+        Self {
+            history,
+            keymap,
+            theme,
+        }
+    }
+}
+
+impl Component for HistoryTab {
+    fn event(&mut self, _ev: KeyEvent) -> Action {
+        Action::None
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(100)])
             .split(r);
 
-        let temp_block = Block::default().borders(Borders::ALL);
-        f.render_widget(temp_block, chunks[0]);
+        let entries = self.history.entries();
+
+        // Newest requests first
+        let rows = entries
+            .iter()
+            .rev()
+            .map(|entry| {
+                let color = self.theme.outcome(&entry.outcome);
+
+                Row::new(vec![
+                    Cell::from(entry.sent_at.format("%H:%M:%S").to_string()),
+                    Cell::from(entry.method.as_str()),
+                    Cell::from(entry.url.as_str()),
+                    Cell::from(entry.outcome.to_string()).style(self.theme.fg(color)),
+                    Cell::from(entry.elapsed.map(format_elapsed).unwrap_or_default()),
+                    Cell::from(
+                        entry
+                            .metrics
+                            .as_ref()
+                            .map(|m| format_size(m.sizes.response_body))
+                            .unwrap_or_default(),
+                    ),
+                ])
+            })
+            .collect::<Vec<Row>>();
+
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Time", "Method", "URL", "Result", "Duration", "Size"])
+                    .style(self.theme.fg(self.theme.heading)),
+            )
+            .block(Block::default().borders(Borders::ALL).title("History"))
+            .widths(&[
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Percentage(50),
+                Constraint::Percentage(20),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ]);
+
+        f.render_widget(table, chunks[0]);
+    }
+
+    fn commands(&self) -> Vec<CommandType> {
+        vec![
+            self.keymap.hint("Tabs", &Shortcut::TABS),
+            CommandType::Break,
+            self.keymap.hint("Help", &[Shortcut::Help]),
+        ]
     }
 }
@@ -1,20 +1,44 @@
-use crossterm::event::{KeyCode, KeyEvent};
-use serde_json::{to_string, Map, Value};
-use std::error::Error;
+use crossterm::event::KeyEvent;
+use serde_json::Map;
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use tokio::task::JoinHandle;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
 
-use crate::components::{CommandType, FolderPopup, ListComponent, MainPaneComponent};
+use crate::components::{
+    folder_popup::centered_rect, Action, CommandType, Component, FolderPopup, ListComponent,
+    MainPaneComponent, MethodPopup, Toast,
+};
+use crate::error::Result;
+use crate::events::{Message, MessageSender};
+use crate::foldertree::EndpointEntry;
+use crate::http::{prepare_request, query_request, Outcome, RequestContext};
+use crate::keymap::{Keymap, Scope};
+use crate::theme::Theme;
+
+// A request that was sent and hasn't finished yet
+struct InFlight {
+    id: u64,
+    started: Instant,
+    handle: JoinHandle<()>,
+}
 
 pub struct MainTab<'a> {
     list_component: ListComponent,
     main_pane: MainPaneComponent<'a>,
     folder_popup: FolderPopup<'a>,
+    method_popup: MethodPopup<'a>,
     focus: Focus,
-    pub current_cmds: Vec<CommandType>,
+    context: RequestContext,
+
+    // Requests still running, by endpoint path
+    in_flight: HashMap<String, InFlight>,
+
+    // Tells the main loop when a request is done
+    tx: MessageSender,
 }
 
 #[derive(PartialEq)]
@@ -22,121 +46,232 @@ enum Focus {
     FolderTreeWindow,
     MainPane, // This will be changed later on
     FolderPopup,
-}
-
-struct Request {
-    method: String,
-    url: String,
-    // TODO: Should this be String or Value?
-    json_body: Value,
+    MethodPopup(Box<Focus>),
 }
 
 impl MainTab<'_> {
-    pub fn new() -> Self {
-        let list_component = ListComponent::new("./config.json");
-        let current_cmds = list_component.generate_cmds();
+    pub fn new(
+        collection: &str,
+        context: RequestContext,
+        keymap: Arc<Keymap>,
+        theme: Arc<Theme>,
+        tx: MessageSender,
+    ) -> Self {
+        // A broken collection shouldn't keep the app from starting, the tree
+        // fills up once the file is fixed
+        let list_component = ListComponent::new(collection, keymap.clone(), theme.clone())
+            .unwrap_or_else(|e| {
+                log::error!("Couldn't load {}: {}", collection, e);
+                let _ = tx.send(Message::Toast(Toast::from(e)));
+
+                ListComponent::empty(collection, keymap.clone(), theme.clone())
+            });
 
         Self {
             list_component,
-            current_cmds,
-            folder_popup: FolderPopup::new(),
-            main_pane: MainPaneComponent::new(),
+            folder_popup: FolderPopup::new(theme.clone()),
+            method_popup: MethodPopup::new(theme.clone()),
+            main_pane: MainPaneComponent::new(keymap, theme),
             focus: Focus::MainPane,
+            context,
+            in_flight: HashMap::new(),
+            tx,
         }
     }
 
-    pub fn event(&mut self, ev: KeyEvent) {
-        // TODO: This shit needs refactor
-        match self.focus {
-            Focus::FolderTreeWindow => {
-                let can_unfold = self.list_component.tree().can_unfold_folder();
-
-                if ev.code == KeyCode::Right && !can_unfold {
-                    self.switch_focus(Focus::MainPane);
-                    self.current_cmds = self.main_pane.generate_cmds();
-                } else {
-                    self.list_component.event(ev);
-
-                    if let Some(curr) = self.list_component.tree().get_current_endpoint() {
-                        self.main_pane.current_endpoint = curr;
-                    }
+    // Applies what the focused component asked for. Actions that concern more
+    // than this tab are handed back to the app.
+    pub fn dispatch(&mut self, action: Action) -> Action {
+        match action {
+            Action::FocusTree => self.switch_focus(Focus::FolderTreeWindow),
+            Action::FocusMainPane => self.switch_focus(Focus::MainPane),
+            Action::SelectionChanged => {
+                if let Some(curr) = self.list_component.tree().get_current_endpoint() {
+                    self.main_pane.current_endpoint = curr;
+                    self.main_pane.current_defaults =
+                        self.list_component.tree().get_current_defaults();
                 }
-                if ev.code == KeyCode::Char('a') {
-                    self.folder_popup.is_open = !self.folder_popup.is_open;
-                    self.switch_focus(Focus::FolderPopup);
+            }
+            Action::SelectEndpoint(path) => {
+                if let Err(e) = self.list_component.tree().select_path(&path) {
+                    return Action::Toast(Toast::from(e));
                 }
+
+                self.switch_focus(Focus::FolderTreeWindow);
+                self.dispatch(Action::SelectionChanged);
             }
-            Focus::MainPane => {
-                if ev.code == KeyCode::Left {
-                    self.switch_focus(Focus::FolderTreeWindow);
-                    self.current_cmds = self.list_component.generate_cmds();
+            Action::OpenAddEndpoint => {
+                self.folder_popup.is_open = true;
+                self.switch_focus(Focus::FolderPopup);
+            }
+            Action::CloseAddEndpoint => {
+                self.folder_popup.close();
+                self.switch_focus(Focus::FolderTreeWindow);
+            }
+            Action::AddEndpoint => {
+                self.folder_popup.close();
+                self.switch_focus(Focus::FolderTreeWindow);
+
+                if let Err(e) = self.list_component.tree().insert_endpoint() {
+                    return Action::Toast(Toast::from(e));
                 }
-                if ev.code == KeyCode::Char('s') {
-                    let endpoint = self.list_component.tree().get_current_endpoint();
-                    let endpoint = match endpoint {
-                        Some(e) => e,
-                        None => Map::new(),
-                    };
-
-                    let request = prepare_request(endpoint);
-
-                    tokio::spawn(async move {
-                        let query = query_request(request).await;
-                        match query {
-                            Ok(r) => {
-                                log::info!("{}", r);
-                            }
-                            Err(e) => {
-                                log::error!("{}", e);
-                            }
-                        }
-                    });
+            }
+            Action::OpenMethodPicker => {
+                if let Some(endpoint) = self.list_component.tree().get_current_endpoint() {
+                    let method = endpoint
+                        .get("method")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or_default();
+                    self.method_popup.open(method);
+
+                    let previous = std::mem::replace(&mut self.focus, Focus::FolderPopup);
+                    self.switch_focus(Focus::MethodPopup(Box::new(previous)));
                 }
             }
-            Focus::FolderPopup => {
-                if ev.code == KeyCode::Esc {
-                    self.folder_popup.close();
-                    self.switch_focus(Focus::FolderTreeWindow);
+            Action::SetMethod(method) => {
+                self.dispatch(Action::CloseMethodPicker);
+
+                if let Err(e) = self.list_component.tree().set_method(&method) {
+                    return Action::Toast(Toast::from(e));
                 }
 
-                self.folder_popup.event(ev);
+                if let Some(curr) = self.list_component.tree().get_current_endpoint() {
+                    self.main_pane.current_endpoint = curr;
+                }
+            }
+            Action::CloseMethodPicker => {
+                self.method_popup.close();
 
-                if self.folder_popup.is_saved() == true {
-                    self.folder_popup.close();
-                    self.switch_focus(Focus::FolderTreeWindow);
+                if let Focus::MethodPopup(previous) =
+                    std::mem::replace(&mut self.focus, Focus::FolderPopup)
+                {
+                    self.switch_focus(*previous);
+                }
+            }
+            Action::SetBody(body) => {
+                if let Err(e) = self.list_component.tree().set_body(&body) {
+                    return Action::Toast(Toast::from(e));
+                }
 
-                    self.list_component.tree().insert_endpoint();
+                if let Some(curr) = self.list_component.tree().get_current_endpoint() {
+                    self.main_pane.current_endpoint = curr;
                 }
             }
+            Action::SendRequest => self.send_request(),
+            Action::CancelRequest => self.cancel_request(),
+            action => return action,
+        }
+
+        Action::None
+    }
+
+    fn send_request(&mut self) {
+        let path = self.list_component.tree().get_current_path();
+        let path = path.unwrap_or_default();
+
+        if self.in_flight.contains_key(&path) {
+            log::warn!("A request for this endpoint is still running");
+            return;
+        }
+
+        let endpoint = self.list_component.tree().get_current_endpoint();
+        let endpoint = match endpoint {
+            Some(e) => e,
+            None => Map::new(),
         };
+
+        let defaults = self.list_component.tree().get_current_defaults();
+        let request = prepare_request(endpoint, defaults);
+        let context = self.context.clone();
+
+        let history = self.context.history.clone();
+        let id = history.start(&path, &request.method, &request.url);
+
+        let tx = self.tx.clone();
+        let endpoint = path.clone();
+
+        let handle = tokio::spawn(async move {
+            let (outcome, response) = match query_request(request, context).await {
+                Ok(r) => {
+                    log::info!("{} ({} bytes)", r.status, r.body.len());
+                    (Outcome::Status(r.status.as_u16()), Some(r))
+                }
+                Err(e) => {
+                    log::error!("{}", e);
+
+                    // The app may be shutting down already
+                    let _ = tx.send(Message::Toast(Toast::from(&e)));
+                    (Outcome::Failed(e.to_string()), None)
+                }
+            };
+
+            history.finish(id, outcome, response);
+
+            let _ = tx.send(Message::RequestFinished { endpoint, id });
+        });
+
+        self.in_flight.insert(
+            path,
+            InFlight {
+                id,
+                started: Instant::now(),
+                handle,
+            },
+        );
     }
 
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
-            .split(r);
+    pub fn request_finished(&mut self, endpoint: &str, id: u64) {
+        // A newer request may have been sent after this one was cancelled
+        if self.in_flight.get(endpoint).is_some_and(|r| r.id == id) {
+            self.in_flight.remove(endpoint);
+        }
+    }
 
-        self.list_component.draw(f, chunks[0]);
-        self.main_pane.draw(f, chunks[1]);
+    pub fn reload_collection(&mut self) -> Result<()> {
+        self.list_component.tree().reload()?;
+        self.dispatch(Action::SelectionChanged);
 
-        let centered = self.folder_popup.centered_rect(60, 60, f.size());
-        self.folder_popup.draw(f, centered);
+        Ok(())
+    }
+
+    pub fn endpoints(&mut self) -> Vec<EndpointEntry> {
+        self.list_component.tree().endpoints()
+    }
+
+    // Spinners of running requests have to be redrawn even when nothing else happens
+    pub fn is_animating(&self) -> bool {
+        !self.in_flight.is_empty()
+    }
+
+    fn cancel_request(&mut self) {
+        let path = self.list_component.tree().get_current_path();
+
+        if let Some(request) = path.and_then(|p| self.in_flight.remove(&p)) {
+            request.handle.abort();
+
+            if self
+                .context
+                .history
+                .finish(request.id, Outcome::Cancelled, None)
+            {
+                log::info!("Request cancelled");
+            }
+        }
     }
 
     fn switch_focus(&mut self, f: Focus) {
         match f {
             Focus::FolderTreeWindow => {
                 self.list_component.set_focus(true);
-                self.main_pane.focused = false;
+                self.main_pane.set_focus(false);
             }
             Focus::MainPane => {
                 self.list_component.set_focus(false);
-                self.main_pane.focused = true;
+                self.main_pane.set_focus(true);
             }
-            Focus::FolderPopup => {
+            Focus::FolderPopup | Focus::MethodPopup(_) => {
                 self.list_component.set_focus(false);
-                self.main_pane.focused = false;
+                self.main_pane.set_focus(false);
             }
         }
 
@@ -144,43 +279,73 @@ impl MainTab<'_> {
     }
 }
 
-fn prepare_request(input: Map<String, Value>) -> Request {
-    let empty = || String::from("");
-    let get_val = |val: &Value| to_string(val).unwrap();
+impl Component for MainTab<'_> {
+    fn event(&mut self, ev: KeyEvent) -> Action {
+        let action = match self.focus {
+            Focus::FolderTreeWindow => self.list_component.event(ev),
+            Focus::MainPane => self.main_pane.event(ev),
+            Focus::FolderPopup => self.folder_popup.event(ev),
+            Focus::MethodPopup(_) => self.method_popup.event(ev),
+        };
 
-    let url = input
-        .get("url")
-        .map_or_else(empty, get_val)
-        .replace("\"", "");
-    let method = input
-        .get("method")
-        .map_or_else(empty, get_val)
-        .replace("\"", "");
+        self.dispatch(action)
+    }
 
-    let json_body = input.get("json_body").map_or_else(empty, get_val);
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
+        self.list_component.pending = self
+            .in_flight
+            .iter()
+            .map(|(path, request)| (path.clone(), request.started.elapsed()))
+            .collect();
 
-    let json_body = serde_json::from_str(json_body.as_str()).unwrap();
+        let path = self.list_component.tree().get_current_path();
+        let path = path.unwrap_or_default();
 
-    Request {
-        url,
-        method,
-        json_body,
-    }
-}
+        self.main_pane.pending = self
+            .in_flight
+            .get(&path)
+            .map(|request| request.started.elapsed());
+        self.main_pane.last_request = self.context.history.last_for(&path);
 
-async fn query_request(input: Request) -> Result<String, Box<dyn Error>> {
-    let client = reqwest::Client::new();
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(r);
 
-    let request = match input.method.as_str() {
-        "POST" => client.post(input.url),
-        "GET" => client.get(input.url),
-        "PUT" => client.put(input.url),
-        "DELETE" => client.delete(input.url),
-        _ => return Err("No method found")?,
-    };
+        self.list_component.draw(f, chunks[0]);
+        self.main_pane.draw(f, chunks[1]);
 
-    // let response = request.json(&input.json_body).send().await?.json().await?;
-    let response = request.send().await?;
+        let centered = centered_rect(60, 60, f.size());
+        self.folder_popup.draw(f, centered);
 
-    Ok(format!("{:#?}", response.status()))
+        let centered = centered_rect(30, 50, f.size());
+        self.method_popup.draw(f, centered);
+    }
+
+    fn commands(&self) -> Vec<CommandType> {
+        match self.focus {
+            Focus::FolderTreeWindow => self.list_component.commands(),
+            Focus::MainPane => self.main_pane.commands(),
+            Focus::FolderPopup | Focus::MethodPopup(_) => Vec::new(),
+        }
+    }
+
+    // Popups take all keys, so there's nothing scoped to list for them
+    fn scope(&self) -> Option<Scope> {
+        match self.focus {
+            Focus::FolderTreeWindow => self.list_component.scope(),
+            Focus::MainPane => self.main_pane.scope(),
+            Focus::FolderPopup | Focus::MethodPopup(_) => None,
+        }
+    }
+
+    // Typing into a filter or search bar, a new endpoint or a custom method
+    fn is_editing(&self) -> bool {
+        match self.focus {
+            Focus::FolderTreeWindow => self.list_component.is_editing(),
+            Focus::MainPane => self.main_pane.is_editing(),
+            Focus::FolderPopup => true,
+            Focus::MethodPopup(_) => self.method_popup.is_editing(),
+        }
+    }
 }
@@ -1,7 +1,9 @@
+pub mod cookiestab;
 pub mod debugtab;
 pub mod historytab;
 pub mod maintab;
 
+pub use cookiestab::CookiesTab;
 pub use debugtab::DebugTab;
 pub use historytab::HistoryTab;
 pub use maintab::MainTab;
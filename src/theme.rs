@@ -0,0 +1,570 @@
+use std::{convert::TryFrom, env, fs, io, path::Path};
+use tui::style::{Color, Modifier, Style};
+
+use crate::error::{Error, Result};
+use crate::http::Outcome;
+
+// Colors by what they mean rather than where they're used, so a preset or
+// the theme file changes them everywhere at once
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub method_get: Color,
+    pub method_post: Color,
+    pub method_put: Color,
+    pub method_patch: Color,
+    pub method_delete: Color,
+    pub method_head: Color,
+    pub method_options: Color,
+    pub method_trace: Color,
+    pub method_connect: Color,
+    // Custom verbs
+    pub method_other: Color,
+
+    pub status_2xx: Color,
+    pub status_3xx: Color,
+    pub status_4xx: Color,
+    pub status_5xx: Color,
+
+    pub json_key: Color,
+    pub json_string: Color,
+    pub json_number: Color,
+    // true, false and null
+    pub json_literal: Color,
+    pub json_punctuation: Color,
+
+    // XML and HTML, their values and brackets use the JSON colors
+    pub markup_tag: Color,
+    pub markup_attribute: Color,
+
+    pub focus_border: Color,
+    // Background of the line under the cursor
+    pub selection: Color,
+    // Characters a filter or the palette matched
+    pub highlight: Color,
+    pub muted: Color,
+    pub label: Color,
+    pub heading: Color,
+    pub command_bar: Color,
+    pub command_bar_text: Color,
+    pub search_match: Color,
+    pub search_current: Color,
+    pub search_text: Color,
+
+    pub error: Color,
+    pub warning: Color,
+    pub success: Color,
+    pub info: Color,
+
+    pub timing_dns: Color,
+    pub timing_connect: Color,
+    pub timing_tls: Color,
+    pub timing_waiting: Color,
+    pub timing_download: Color,
+
+    // NO_COLOR is set: every color is the terminal's own and what needs to
+    // stand out is reversed, bold or underlined instead
+    monochrome: bool,
+}
+
+// Presets a theme file can start from
+pub const PRESETS: [&str; 3] = ["dark", "light", "high-contrast"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            method_get: Color::LightYellow,
+            method_post: Color::Green,
+            method_put: Color::Blue,
+            method_patch: Color::Magenta,
+            method_delete: Color::Red,
+            method_head: Color::Cyan,
+            method_options: Color::LightBlue,
+            method_trace: Color::Gray,
+            method_connect: Color::LightRed,
+            method_other: Color::LightMagenta,
+
+            status_2xx: Color::Green,
+            status_3xx: Color::Cyan,
+            status_4xx: Color::LightRed,
+            status_5xx: Color::Red,
+
+            json_key: Color::Cyan,
+            json_string: Color::Green,
+            json_number: Color::Yellow,
+            json_literal: Color::Magenta,
+            json_punctuation: Color::Gray,
+
+            markup_tag: Color::Cyan,
+            markup_attribute: Color::Yellow,
+
+            focus_border: Color::Reset,
+            selection: Color::DarkGray,
+            highlight: Color::Yellow,
+            muted: Color::Gray,
+            label: Color::Cyan,
+            heading: Color::Yellow,
+            command_bar: Color::Blue,
+            command_bar_text: Color::Black,
+            search_match: Color::Yellow,
+            search_current: Color::LightRed,
+            search_text: Color::Black,
+
+            error: Color::Red,
+            warning: Color::Yellow,
+            success: Color::Green,
+            info: Color::Blue,
+
+            timing_dns: Color::Cyan,
+            timing_connect: Color::Yellow,
+            timing_tls: Color::Magenta,
+            timing_waiting: Color::Green,
+            timing_download: Color::Blue,
+
+            monochrome: false,
+        }
+    }
+
+    // For terminals with a light background, nothing yellow or light on it
+    pub fn light() -> Self {
+        Theme {
+            method_get: Color::Blue,
+            method_post: Color::Green,
+            method_put: Color::Magenta,
+            method_patch: Color::Indexed(130),
+            method_delete: Color::Red,
+            method_head: Color::Cyan,
+            method_options: Color::Indexed(25),
+            method_trace: Color::DarkGray,
+            method_connect: Color::Indexed(88),
+            method_other: Color::Indexed(90),
+
+            status_2xx: Color::Green,
+            status_3xx: Color::Blue,
+            status_4xx: Color::Indexed(130),
+            status_5xx: Color::Red,
+
+            json_key: Color::Blue,
+            json_string: Color::Green,
+            json_number: Color::Magenta,
+            json_literal: Color::Indexed(130),
+            json_punctuation: Color::DarkGray,
+
+            markup_tag: Color::Blue,
+            markup_attribute: Color::Magenta,
+
+            focus_border: Color::Blue,
+            selection: Color::Gray,
+            highlight: Color::Magenta,
+            muted: Color::DarkGray,
+            label: Color::Blue,
+            heading: Color::Magenta,
+            command_bar: Color::Blue,
+            command_bar_text: Color::White,
+            search_match: Color::Yellow,
+            search_current: Color::LightRed,
+            search_text: Color::Black,
+
+            error: Color::Red,
+            warning: Color::Indexed(130),
+            success: Color::Green,
+            info: Color::Blue,
+
+            timing_dns: Color::Cyan,
+            timing_connect: Color::Indexed(130),
+            timing_tls: Color::Magenta,
+            timing_waiting: Color::Green,
+            timing_download: Color::Blue,
+
+            monochrome: false,
+        }
+    }
+
+    // Only the bright colors, on a dark background
+    pub fn high_contrast() -> Self {
+        Theme {
+            method_get: Color::LightYellow,
+            method_post: Color::LightGreen,
+            method_put: Color::LightCyan,
+            method_patch: Color::LightMagenta,
+            method_delete: Color::LightRed,
+            method_head: Color::LightBlue,
+            method_options: Color::Indexed(214),
+            method_trace: Color::White,
+            method_connect: Color::Indexed(219),
+            method_other: Color::Indexed(183),
+
+            status_2xx: Color::LightGreen,
+            status_3xx: Color::LightCyan,
+            status_4xx: Color::LightYellow,
+            status_5xx: Color::LightRed,
+
+            json_key: Color::LightCyan,
+            json_string: Color::LightGreen,
+            json_number: Color::LightYellow,
+            json_literal: Color::LightMagenta,
+            json_punctuation: Color::White,
+
+            markup_tag: Color::LightCyan,
+            markup_attribute: Color::LightYellow,
+
+            focus_border: Color::LightYellow,
+            selection: Color::Blue,
+            highlight: Color::LightYellow,
+            muted: Color::White,
+            label: Color::LightCyan,
+            heading: Color::LightYellow,
+            command_bar: Color::White,
+            command_bar_text: Color::Black,
+            search_match: Color::LightYellow,
+            search_current: Color::LightRed,
+            search_text: Color::Black,
+
+            error: Color::LightRed,
+            warning: Color::LightYellow,
+            success: Color::LightGreen,
+            info: Color::LightCyan,
+
+            timing_dns: Color::LightCyan,
+            timing_connect: Color::LightYellow,
+            timing_tls: Color::LightMagenta,
+            timing_waiting: Color::LightGreen,
+            timing_download: Color::LightBlue,
+
+            monochrome: false,
+        }
+    }
+
+    pub fn no_color() -> Self {
+        let mut theme = Self::dark();
+        for (_, color) in theme.colors_mut() {
+            *color = Color::Reset;
+        }
+        theme.monochrome = true;
+
+        theme
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    // No colors at all when NO_COLOR is set, whatever the file says, and the
+    // dark preset when there's no theme file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Ok(Self::no_color());
+        }
+
+        match fs::read_to_string(path) {
+            Ok(input) => Self::from_toml(&input),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // A preset to start from and the colors that differ from it, by name,
+    // index or hex:
+    //
+    //   preset = "light"
+    //   method_post = "green"
+    //   selection = 236
+    //   highlight = "#ffaf00"
+    pub fn from_toml(input: &str) -> Result<Self> {
+        let table: toml::value::Table =
+            toml::from_str(input).map_err(|e| Error::Config(format!("Invalid theme: {}", e)))?;
+
+        let mut theme = match table.get("preset") {
+            None => Self::default(),
+            Some(toml::Value::String(name)) => Self::preset(name).ok_or_else(|| {
+                Error::Config(format!(
+                    "Unknown preset {:?}, one of {} is expected",
+                    name,
+                    PRESETS.join(", ")
+                ))
+            })?,
+            Some(_) => return Err(Error::Config(String::from("preset has to be a name"))),
+        };
+
+        for (name, value) in table.iter().filter(|(name, _)| *name != "preset") {
+            let color = match value {
+                toml::Value::String(s) => parse_color(s),
+                toml::Value::Integer(i) => u8::try_from(*i).ok().map(Color::Indexed),
+                _ => None,
+            }
+            .ok_or_else(|| Error::Config(format!("Invalid color {} for {}", value, name)))?;
+
+            let slot = theme
+                .colors_mut()
+                .into_iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, slot)| slot)
+                .ok_or_else(|| Error::Config(format!("Unknown theme color {:?}", name)))?;
+
+            *slot = color;
+        }
+
+        Ok(theme)
+    }
+
+    // Every color with its name in the theme file
+    fn colors_mut(&mut self) -> Vec<(&'static str, &mut Color)> {
+        vec![
+            ("method_get", &mut self.method_get),
+            ("method_post", &mut self.method_post),
+            ("method_put", &mut self.method_put),
+            ("method_patch", &mut self.method_patch),
+            ("method_delete", &mut self.method_delete),
+            ("method_head", &mut self.method_head),
+            ("method_options", &mut self.method_options),
+            ("method_trace", &mut self.method_trace),
+            ("method_connect", &mut self.method_connect),
+            ("method_other", &mut self.method_other),
+            ("status_2xx", &mut self.status_2xx),
+            ("status_3xx", &mut self.status_3xx),
+            ("status_4xx", &mut self.status_4xx),
+            ("status_5xx", &mut self.status_5xx),
+            ("json_key", &mut self.json_key),
+            ("json_string", &mut self.json_string),
+            ("json_number", &mut self.json_number),
+            ("json_literal", &mut self.json_literal),
+            ("json_punctuation", &mut self.json_punctuation),
+            ("markup_tag", &mut self.markup_tag),
+            ("markup_attribute", &mut self.markup_attribute),
+            ("focus_border", &mut self.focus_border),
+            ("selection", &mut self.selection),
+            ("highlight", &mut self.highlight),
+            ("muted", &mut self.muted),
+            ("label", &mut self.label),
+            ("heading", &mut self.heading),
+            ("command_bar", &mut self.command_bar),
+            ("command_bar_text", &mut self.command_bar_text),
+            ("search_match", &mut self.search_match),
+            ("search_current", &mut self.search_current),
+            ("search_text", &mut self.search_text),
+            ("error", &mut self.error),
+            ("warning", &mut self.warning),
+            ("success", &mut self.success),
+            ("info", &mut self.info),
+            ("timing_dns", &mut self.timing_dns),
+            ("timing_connect", &mut self.timing_connect),
+            ("timing_tls", &mut self.timing_tls),
+            ("timing_waiting", &mut self.timing_waiting),
+            ("timing_download", &mut self.timing_download),
+        ]
+    }
+
+    pub fn fg(&self, color: Color) -> Style {
+        Style::default().fg(color)
+    }
+
+    pub fn method(&self, method: &str) -> Color {
+        match method.trim().to_ascii_uppercase().as_str() {
+            "GET" => self.method_get,
+            "POST" => self.method_post,
+            "PUT" => self.method_put,
+            "PATCH" => self.method_patch,
+            "DELETE" => self.method_delete,
+            "HEAD" => self.method_head,
+            "OPTIONS" => self.method_options,
+            "TRACE" => self.method_trace,
+            "CONNECT" => self.method_connect,
+            _ => self.method_other,
+        }
+    }
+
+    pub fn status(&self, status: u16) -> Color {
+        match status {
+            0..=299 => self.status_2xx,
+            300..=399 => self.status_3xx,
+            400..=499 => self.status_4xx,
+            _ => self.status_5xx,
+        }
+    }
+
+    // How a request went, by its status class
+    pub fn outcome(&self, outcome: &Outcome) -> Color {
+        match outcome {
+            Outcome::Status(status) => self.status(*status),
+            Outcome::Failed(_) => self.error,
+            Outcome::Pending => self.warning,
+            Outcome::Cancelled => self.muted,
+        }
+    }
+
+    // Phase of a request as named by `Timings::phases`
+    pub fn timing(&self, phase: &str) -> Color {
+        match phase {
+            "DNS" => self.timing_dns,
+            "Connect" => self.timing_connect,
+            "TLS" => self.timing_tls,
+            "Waiting" => self.timing_waiting,
+            _ => self.timing_download,
+        }
+    }
+
+    fn emphasis(&self, style: Style, modifier: Modifier) -> Style {
+        match self.monochrome {
+            true => style.add_modifier(modifier),
+            false => style,
+        }
+    }
+
+    pub fn border(&self, focused: bool) -> Style {
+        match focused {
+            true => self.fg(self.focus_border),
+            false => Style::default(),
+        }
+    }
+
+    pub fn selected(&self) -> Style {
+        let style = Style::default().bg(self.selection);
+        self.emphasis(style, Modifier::REVERSED)
+    }
+
+    pub fn highlighted(&self) -> Style {
+        let style = self.fg(self.highlight).add_modifier(Modifier::BOLD);
+        self.emphasis(style, Modifier::UNDERLINED)
+    }
+
+    pub fn command(&self) -> Style {
+        let style = Style::default()
+            .bg(self.command_bar)
+            .fg(self.command_bar_text);
+        self.emphasis(style, Modifier::REVERSED)
+    }
+
+    // The current match stands out from the others without colors by being
+    // reversed rather than underlined
+    pub fn search_match(&self, current: bool) -> Style {
+        let bg = match current {
+            true => self.search_current,
+            false => self.search_match,
+        };
+        let style = Style::default().fg(self.search_text).bg(bg);
+
+        match current {
+            true => self.emphasis(style, Modifier::REVERSED),
+            false => self.emphasis(style, Modifier::UNDERLINED),
+        }
+    }
+}
+
+// Color names like "lightblue" or "dark-gray", "#rrggbb" or a 0-255 index
+fn parse_color(s: &str) -> Option<Color> {
+    let name = s.trim().to_ascii_lowercase().replace(['-', '_', ' '], "");
+
+    let color = match name.as_str() {
+        "reset" | "default" | "none" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Color::Rgb(channel(1)?, channel(3)?, channel(5)?)
+        }
+        index => Color::Indexed(index.parse().ok()?),
+    };
+
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("LightBlue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("dark-grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("236"), Some(Color::Indexed(236)));
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("mauve"), None);
+    }
+
+    #[test]
+    fn test_theme_file() {
+        let theme = Theme::from_toml("").unwrap();
+        assert_eq!(theme, Theme::dark());
+
+        let theme =
+            Theme::from_toml("preset = \"light\"\nmethod_post = \"#00ff00\"\nselection = 236")
+                .unwrap();
+        assert_eq!(theme.method("post"), Color::Rgb(0, 255, 0));
+        assert_eq!(theme.selection, Color::Indexed(236));
+        assert_eq!(theme.json_key, Theme::light().json_key);
+
+        for preset in PRESETS.iter() {
+            assert!(Theme::preset(preset).is_some());
+        }
+
+        assert!(Theme::from_toml("preset = \"solarized\"").is_err());
+        assert!(Theme::from_toml("method_pots = \"red\"").is_err());
+        assert!(Theme::from_toml("error = 300").is_err());
+        assert!(Theme::from_toml("error = true").is_err());
+    }
+
+    #[test]
+    fn test_method_colors_differ() {
+        for preset in PRESETS.iter() {
+            let theme = Theme::preset(preset).unwrap();
+            let colors = [
+                theme.method_get,
+                theme.method_post,
+                theme.method_put,
+                theme.method_patch,
+                theme.method_delete,
+                theme.method_head,
+                theme.method_options,
+                theme.method_trace,
+                theme.method_connect,
+                theme.method_other,
+            ];
+
+            for (i, a) in colors.iter().enumerate() {
+                for b in &colors[i + 1..] {
+                    assert_ne!(a, b, "{} uses {:?} for two methods", preset, a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_color() {
+        let theme = Theme::no_color();
+        assert_eq!(theme.method("GET"), Color::Reset);
+        assert_eq!(theme.status(500), Color::Reset);
+        assert!(theme.selected().add_modifier.contains(Modifier::REVERSED));
+        assert!(!Theme::dark()
+            .selected()
+            .add_modifier
+            .contains(Modifier::REVERSED));
+
+        // Matches stay apart from the current one without colors
+        assert_ne!(theme.search_match(true), theme.search_match(false));
+    }
+}